@@ -0,0 +1,148 @@
+use crate::config::Config;
+use crate::index::FileIndex;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+use xxhash_rust::xxh3::{xxh3_64, Xxh3Default};
+
+/// Version of [`FileIndex`], bumped whenever its layout or what is extracted into it
+/// changes for the entries of the previous versions not to be read
+const INDEX_VERSION: u32 = 7;
+
+/// The cached index of a file, with what it was built from
+#[derive(Serialize, Deserialize)]
+struct CacheEntry<I> {
+    content_hash: u64,
+    config_hash: u64,
+    index: I,
+}
+
+/// On-disk cache of the file indexes, so a run only parses the files that changed since
+/// the previous one
+///
+/// An entry is keyed by the path of its file, and only used while the contents of the
+/// file and the config it was resolved with are the same.
+pub struct IndexCache {
+    dir: PathBuf,
+    /// Hash of everything besides the contents of a file its index depends on
+    config_hash: u64,
+}
+
+impl IndexCache {
+    pub fn new(dir: PathBuf, root_path: &Path, config: &Config) -> Self {
+        let mut hasher = Xxh3Default::new();
+
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        INDEX_VERSION.hash(&mut hasher);
+        root_path.hash(&mut hasher);
+        config.extensions.hash(&mut hasher);
+        config.aliases.hash(&mut hasher);
+        config.conditions.hash(&mut hasher);
+        config.events.hash(&mut hasher);
+        config.granularity.hash(&mut hasher);
+
+        for name in ["tsconfig.json", "jsconfig.json"] {
+            std::fs::read(root_path.join(name)).ok().hash(&mut hasher);
+        }
+
+        Self {
+            dir,
+            config_hash: hasher.finish(),
+        }
+    }
+
+    /// The cached index of the file at `source_path`, unless its contents or the config
+    /// changed, or one of its imports resolves differently now
+    pub fn load(
+        &self,
+        source_path: &Path,
+        source_text: &str,
+        resolve: impl Fn(&str) -> Option<PathBuf>,
+    ) -> Option<FileIndex> {
+        let bytes = std::fs::read(self.entry_path(source_path)).ok()?;
+        let entry: CacheEntry<FileIndex> = bincode::deserialize(&bytes).ok()?;
+
+        if entry.content_hash != xxh3_64(source_text.as_bytes())
+            || entry.config_hash != self.config_hash
+        {
+            return None;
+        }
+
+        // the files around this one may have been added or deleted since, e.g. an
+        // `index.ts` next to the `index.js` an import resolved to
+        let is_resolved = entry
+            .index
+            .resolutions
+            .iter()
+            .all(|(specifier, source)| resolve(specifier) == *source);
+
+        is_resolved.then_some(entry.index)
+    }
+
+    pub fn store(&self, source_path: &Path, source_text: &str, index: &FileIndex) -> Result<()> {
+        let entry = CacheEntry {
+            content_hash: xxh3_64(source_text.as_bytes()),
+            config_hash: self.config_hash,
+            index,
+        };
+
+        // written aside and renamed over the entry, so a run reading it concurrently
+        // or one interrupted midway never leaves a truncated entry behind
+        let entry_path = self.entry_path(source_path);
+        let temp_path = entry_path.with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(&temp_path, bincode::serialize(&entry)?)?;
+        std::fs::rename(&temp_path, &entry_path)?;
+
+        Ok(())
+    }
+
+    fn entry_path(&self, source_path: &Path) -> PathBuf {
+        let path_hash = xxh3_64(source_path.as_os_str().as_encoded_bytes());
+
+        self.dir.join(format!("{path_hash:016x}.bin"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module::ParsedModule;
+
+    #[test]
+    fn reuse_index_of_unchanged_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let source_path = dir.path().join("factory.js");
+        let source_text = "import { api } from \"./api\";\nexport const call = () => api();\n";
+        let config = Config::default();
+        let cache = IndexCache::new(dir.path().join("cache"), dir.path(), &config);
+        let api_js = |_: &str| Some(dir.path().join("api.js"));
+
+        let module = ParsedModule::parse(&source_path, source_text.into()).unwrap();
+        let index = module.with_semantic(|semantic| {
+            FileIndex::build(semantic, &config.events, config.granularity, api_js)
+        });
+        cache.store(&source_path, source_text, &index).unwrap();
+
+        let cached = cache.load(&source_path, source_text, api_js).unwrap();
+        let changed = cache.load(&source_path, "export const call = 1;\n", api_js);
+        // an `api.ts` added next to `api.js` now takes precedence
+        let resolved_elsewhere = cache.load(&source_path, source_text, |_| {
+            Some(dir.path().join("api.ts"))
+        });
+        let unresolved = cache.load(&source_path, source_text, |_| None);
+        let entries = std::fs::read_dir(dir.path().join("cache")).unwrap().count();
+
+        assert_eq!(cached.units, index.units);
+        assert_eq!(cached.symbol_id("call"), index.symbol_id("call"));
+        assert_eq!(cached.interface.exports.len(), 1);
+        assert!(changed.is_none());
+        assert!(resolved_elsewhere.is_none());
+        assert!(unresolved.is_none());
+        // only the entry, renamed from its temporary file
+        assert_eq!(entries, 1);
+    }
+}
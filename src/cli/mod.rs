@@ -1,46 +1,339 @@
+mod output;
+mod watch;
+
+use crate::config::{Config, Granularity, OutputFormat, QueryConfig, ReferenceKind};
 use crate::core::Bumblebee;
-use crate::query::Query;
+use crate::query::{DiffQuery, Query};
+use crate::report::ImpactReport;
 
 use anyhow::Result;
-use clap::Parser;
-use oxc_allocator::Allocator;
+use clap::{ArgAction, ArgGroup, Parser, Subcommand};
+use std::io::IsTerminal;
 use std::path::Path;
-use std::path::PathBuf;
+use tracing_subscriber::{filter::LevelFilter, filter::Targets, prelude::*};
 
-#[derive(Parser)]
+#[derive(Parser, Default)]
 #[command(author, version, about, long_about = None)]
+#[command(group(ArgGroup::new("source").args(["symbol_path", "package"])))]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     #[arg(long)]
     pub project_path: String,
 
-    #[arg(long, default_value = "../output")]
-    pub target_path: String,
+    /// Directory the impacted snippets are written to [default: ../output]
+    #[arg(long)]
+    pub target_path: Option<String>,
+
+    /// Config file to use instead of `.bumblebee.toml` in the project path
+    #[arg(long)]
+    pub config: Option<String>,
+
+    /// Symbol to find the impacted areas of, replacing the queries of the config
+    #[arg(long, requires = "source")]
+    pub symbol: Option<String>,
+
+    /// File the symbol is declared in, relative to the project path
+    #[arg(long, requires = "symbol")]
+    pub symbol_path: Option<String>,
+
+    /// Third-party package the symbol is imported from, e.g. `lodash`
+    #[arg(long, requires = "symbol")]
+    pub package: Option<String>,
+
+    /// Global to find the impacted areas of, e.g. `fetch` or `window.localStorage.setItem`
+    #[arg(long, conflicts_with = "symbol")]
+    pub global: Option<String>,
+
+    /// Environment variable to find the impacted areas of, e.g. `API_URL`
+    #[arg(long, conflicts_with_all = ["symbol", "global"])]
+    pub env: Option<String>,
+
+    /// String constant to find the impacted areas of, e.g. a feature flag `checkout.v2`
+    #[arg(long, conflicts_with_all = ["symbol", "global", "env"])]
+    pub string: Option<String>,
+
+    /// Find the impacted areas of the uncommitted changes instead of the queries
+    #[arg(long, conflicts_with_all = ["symbol", "global", "env", "string"])]
+    pub diff: bool,
+
+    /// Only analyze the files matching these globs, relative to the project path
+    #[arg(long)]
+    pub include: Vec<String>,
+
+    /// Skip the files matching these globs, relative to the project path
+    #[arg(long)]
+    pub exclude: Vec<String>,
+
+    /// Analyze a package of `node_modules` like our own code
+    #[arg(long)]
+    pub scan_package: Vec<String>,
+
+    #[arg(long, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// How much code around the impacted references is reported [default: top-level]
+    #[arg(long, value_enum)]
+    pub granularity: Option<Granularity>,
+
+    /// How many hops the references are followed from the queried symbols
+    #[arg(long)]
+    pub max_depth: Option<usize>,
+
+    /// Only spread the impact through these kinds of references, e.g. `call`
+    #[arg(long, value_enum)]
+    pub follow: Vec<ReferenceKind>,
+
+    /// Report these kinds of references without spreading the impact through them,
+    /// e.g. `type`
+    #[arg(long, value_enum)]
+    pub ignore: Vec<ReferenceKind>,
+
+    /// Parse every file again instead of reusing the indexes cached by previous runs
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Fail when files couldn't be analyzed completely, e.g. because of syntax errors
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Log what the analysis does, `-vv` down to every impacted symbol
+    #[arg(short, long, action = ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// Only log errors, not the diagnostics of the files
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Subcommand, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Keep the index in memory and print the impact again after every save
+    Watch,
+}
+
+impl Args {
+    /// Level of the logs of the crate: the diagnostics and progress by default, what the
+    /// analysis does with `-v`, every impacted symbol with `-vv`, only errors with `-q`
+    fn log_level(&self) -> LevelFilter {
+        match (self.quiet, self.verbose) {
+            (true, _) => LevelFilter::ERROR,
+            (false, 0) => LevelFilter::INFO,
+            (false, 1) => LevelFilter::DEBUG,
+            (false, _) => LevelFilter::TRACE,
+        }
+    }
+
+    /// Overrides the values of the config with the ones passed on the command line
+    fn merge_into(self, config: &mut Config) {
+        if let Some(symbol) = self.symbol {
+            let query = match (self.symbol_path, self.package) {
+                (Some(symbol_path), _) => QueryConfig::Symbol {
+                    symbol,
+                    path: symbol_path.into(),
+                },
+                (None, Some(package)) => QueryConfig::Package { package, symbol },
+                (None, None) => unreachable!("clap requires a source for the symbol"),
+            };
+
+            config.queries = vec![query];
+        }
+
+        if let Some(global) = self.global {
+            config.queries = vec![QueryConfig::Global { global }];
+        }
+
+        if let Some(env) = self.env {
+            config.queries = vec![QueryConfig::Env { env }];
+        }
+
+        if let Some(string) = self.string {
+            config.queries = vec![QueryConfig::String { string }];
+        }
+
+        if self.diff {
+            config.queries.clear();
+        }
+
+        config.include.extend(self.include);
+        config.exclude.extend(self.exclude);
+        config.scan_packages.extend(self.scan_package);
+        config.follow.extend(self.follow);
+        config.ignore.extend(self.ignore);
+
+        if let Some(format) = self.format {
+            config.output_format = format;
+        }
+
+        if let Some(granularity) = self.granularity {
+            config.granularity = granularity;
+        }
+
+        if let Some(max_depth) = self.max_depth {
+            config.max_depth = Some(max_depth);
+        }
+
+        if self.no_cache {
+            config.cache = false;
+        }
+
+        if self.strict {
+            config.strict = true;
+        }
+    }
 }
 
 pub fn run(args: Args) -> Result<()> {
-    let mut allocator = Allocator::default();
+    init_tracing(&args);
+
     let home = std::env::current_dir()?;
-    let root_path = home.join(args.project_path);
-    let target_dir = Path::new(&args.target_path);
-    let mut bumblebee = Bumblebee::new(&root_path, target_dir, &mut allocator);
+    let root_path = home.join(&args.project_path);
+    let mut config = Config::load(&root_path, args.config.as_ref().map(Path::new))?;
+
+    // a target path in the config is relative to the project, on the command line to
+    // the current directory
+    let target_dir = match (&args.target_path, &config.target_path) {
+        (Some(target_path), _) => home.join(target_path),
+        (None, Some(target_path)) => root_path.join(target_path),
+        (None, None) => home.join("../output"),
+    };
+
+    let command = args.command;
+    let diff = args.diff;
+    args.merge_into(&mut config);
+
+    let mut bumblebee = Bumblebee::builder()
+        .root(&root_path)
+        .config(config.clone())
+        .build()?;
+    let queries: Vec<Query> = config.queries.iter().map(Query::from).collect();
 
-    eval_dir(&mut bumblebee)
+    // the uncommitted changes are diffed again on every analysis
+    let analyze = |bumblebee: &mut Bumblebee| -> Result<ImpactReport> {
+        let mut queries = queries.clone();
+
+        if diff {
+            queries.push(Query::Diff(DiffQuery::uncommitted(bumblebee.root_path())?));
+        }
+
+        let report = bumblebee.analyze(&queries)?;
+        output::print_report(&report, config.output_format, &target_dir)?;
+
+        Ok(report)
+    };
+
+    match command {
+        Some(Command::Watch) => watch::watch(&mut bumblebee, &target_dir, analyze),
+        None => {
+            let report = analyze(&mut bumblebee)?;
+
+            if config.strict && !report.incomplete.is_empty() {
+                anyhow::bail!(
+                    "{} file(s) couldn't be analyzed completely, the impacted areas may be missing",
+                    report.incomplete.len()
+                );
+            }
+
+            Ok(())
+        }
+    }
 }
 
-fn eval_dir<'a>(bumblebee: &'a mut Bumblebee<'a>) -> Result<()> {
-    let queries = [Query::new_with_symbol(
-        "fellow".into(),
-        PathBuf::from("./pkgs/utils/index.js"),
-    )];
+/// Logs to stderr, stdout being for the report only
+fn init_tracing(args: &Args) {
+    let level = args.log_level();
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_target(false)
+        .without_time();
+
+    // the logs of the dependencies, e.g. of the resolver, aren't ours to show; a
+    // subscriber set before, e.g. by another test, is kept
+    tracing_subscriber::registry()
+        .with(layer)
+        .with(Targets::new().with_target(env!("CARGO_CRATE_NAME"), level))
+        .try_init()
+        .ok();
+}
 
-    for query in queries {
-        println!("{:?}", query);
-        bumblebee.evaluate_query(query);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{capture_logs, project};
+
+    #[test]
+    fn log_levels() {
+        let log_level = |flags: &[&str]| {
+            let args = ["bumblebee", "--project-path", "."].iter().chain(flags);
+            Args::try_parse_from(args).unwrap().log_level()
+        };
+
+        assert_eq!(log_level(&[]), LevelFilter::INFO);
+        assert_eq!(log_level(&["-v"]), LevelFilter::DEBUG);
+        assert_eq!(log_level(&["-vv"]), LevelFilter::TRACE);
+        assert_eq!(log_level(&["--quiet"]), LevelFilter::ERROR);
+        assert!(Args::try_parse_from(["bumblebee", "--project-path", ".", "-v", "-q"]).is_err());
+    }
+
+    #[test]
+    fn merge_followed_reference_kinds() {
+        let args = Args::try_parse_from([
+            "bumblebee",
+            "--project-path",
+            ".",
+            "--follow",
+            "call",
+            "--ignore",
+            "type",
+        ])
+        .unwrap();
+        let mut config = Config::parse("follow = [\"argument\"]\n").unwrap();
+        args.merge_into(&mut config);
+
+        assert_eq!(
+            config.follow,
+            [ReferenceKind::Argument, ReferenceKind::Call]
+        );
+        assert_eq!(config.ignore, [ReferenceKind::Type]);
     }
 
-    bumblebee.update_services()?;
-    bumblebee.find_references_recursively();
-    bumblebee.dump_reference_files();
+    #[test]
+    fn strict_fails_on_incomplete_files() {
+        let dir = project(&[
+            ("cart.js", "export const add = () => {};\n"),
+            (
+                "checkout.js",
+                "import { add } from \"./cart.js\";\nreturn;\nexport const buy = () => add();\n",
+            ),
+        ]);
+        let project_path = dir.path().to_string_lossy();
+        let target_path = dir.path().join("output");
+        let run_with = |flags: &[&str]| {
+            let args = [
+                "bumblebee",
+                "--project-path",
+                &project_path,
+                "--target-path",
+                &target_path.to_string_lossy(),
+                "--symbol",
+                "add",
+                "--symbol-path",
+                "./cart.js",
+                "--no-cache",
+            ];
+
+            run(Args::try_parse_from(args.iter().chain(flags)).unwrap())
+        };
 
-    Ok(())
+        capture_logs();
+        let error = run_with(&["--strict"]).unwrap_err();
+
+        assert!(run_with(&[]).is_ok());
+        assert_eq!(
+            error.to_string(),
+            "1 file(s) couldn't be analyzed completely, the impacted areas may be missing"
+        );
+    }
 }
@@ -0,0 +1,120 @@
+use crate::config::OutputFormat;
+use crate::report::ImpactReport;
+use anyhow::Result;
+use std::{fs::File, io::Write, path::Path};
+
+/// Prints the report in `format` on stdout, and logs the problems of the files
+pub fn print_report(report: &ImpactReport, format: OutputFormat, target_dir: &Path) -> Result<()> {
+    let mut out = Vec::new();
+    write_report(&mut out, report, format, target_dir)?;
+    // through `print!`, which the tests capture unlike the handle of stdout
+    print!("{}", String::from_utf8_lossy(&out));
+
+    Ok(())
+}
+
+/// Writes the report in `format` to `out`, which gets nothing else, e.g. for the JSON to
+/// be piped to another tool
+fn write_report(
+    out: &mut impl Write,
+    report: &ImpactReport,
+    format: OutputFormat,
+    target_dir: &Path,
+) -> Result<()> {
+    match format {
+        OutputFormat::Files => {
+            dump_unit_files(report, target_dir)?;
+
+            for edge in &report.edges {
+                let confidence = if edge.heuristic { " (heuristic)" } else { "" };
+
+                writeln!(
+                    out,
+                    "{} edge `{}`{}: {} -> {}",
+                    edge.kind, edge.key, confidence, edge.from, edge.to
+                )?;
+            }
+
+            for entry_point in &report.entry_points {
+                writeln!(out, "Impacted entry point: {}", entry_point.display())?;
+            }
+        }
+        OutputFormat::Json => writeln!(out, "{}", serde_json::to_string_pretty(report)?)?,
+    }
+
+    // the files with problems are analyzed without the code the problems are about
+    for file_diagnostic in &report.diagnostics {
+        let (path, diagnostic) = (file_diagnostic.path.display(), &file_diagnostic.diagnostic);
+
+        match diagnostic.line {
+            Some(line) => tracing::warn!("{}:{}: {}", path, line, diagnostic.message),
+            None => tracing::warn!("{}: {}", path, diagnostic.message),
+        }
+    }
+
+    for path in &report.incomplete {
+        tracing::warn!("Incomplete analysis of {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Writes the impacted units of every file to the same path under `target_dir`
+fn dump_unit_files(report: &ImpactReport, target_dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(target_dir).ok();
+
+    for (path, units) in report.files() {
+        let target_path = target_dir.join(path);
+
+        if let Some(parent) = target_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut file_stream = File::create(&target_path)?;
+
+        units.iter().for_each(|unit| {
+            file_stream
+                .write_all((unit.text.to_string() + "\n\n").as_bytes())
+                .ok();
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Diagnostic;
+    use crate::report::FileDiagnostic;
+
+    #[test]
+    fn only_the_report_on_stdout() {
+        let dir = tempfile::tempdir().unwrap();
+        let report = ImpactReport {
+            entry_points: vec!["index.js".into()],
+            diagnostics: vec![FileDiagnostic {
+                path: "broken.js".into(),
+                diagnostic: Diagnostic {
+                    line: Some(2),
+                    message: "Syntax error: Unexpected token".into(),
+                },
+            }],
+            incomplete: vec!["broken.js".into()],
+            ..Default::default()
+        };
+        let write = |format| {
+            let mut out = Vec::new();
+            write_report(&mut out, &report, format, dir.path()).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let json: serde_json::Value = serde_json::from_str(&write(OutputFormat::Json)).unwrap();
+
+        assert_eq!(json, serde_json::to_value(&report).unwrap());
+        assert_eq!(
+            write(OutputFormat::Files),
+            "Impacted entry point: index.js\n"
+        );
+    }
+}
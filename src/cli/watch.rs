@@ -0,0 +1,67 @@
+use crate::core::Bumblebee;
+use crate::report::ImpactReport;
+use anyhow::Result;
+use dunce::realpath;
+use notify::{RecursiveMode, Watcher};
+use std::{path::Path, sync::mpsc, time::Duration};
+
+/// How long the other events of a save are waited for, e.g. when an editor writes a
+/// temporary file and renames it
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Analyzes the project, then again after every save, only indexing the saved files
+/// again
+///
+/// A failed analysis or update, e.g. of a file saved halfway, is logged and the project
+/// watched further; only losing the watcher ends it.
+pub fn watch(
+    bumblebee: &mut Bumblebee,
+    target_dir: &Path,
+    mut analyze: impl FnMut(&mut Bumblebee) -> Result<ImpactReport>,
+) -> Result<()> {
+    let root_path = bumblebee.root_path().to_path_buf();
+    // the snippets written by the analysis must not trigger another one
+    let target_dir = realpath(target_dir).unwrap_or_else(|_| target_dir.to_path_buf());
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+
+    watcher.watch(&root_path, RecursiveMode::Recursive)?;
+
+    loop {
+        if let Err(error) = analyze(bumblebee) {
+            tracing::error!("{error:#}");
+        }
+
+        tracing::info!("Watching {} for changes...", root_path.display());
+
+        loop {
+            // wait for the first event of a save, then for the rest of it
+            let mut events = vec![receiver.recv()?];
+
+            while let Ok(event) = receiver.recv_timeout(DEBOUNCE) {
+                events.push(event);
+            }
+
+            let mut paths = Vec::new();
+
+            for event in events {
+                match event {
+                    Ok(event) if !event.kind.is_access() => paths.extend(
+                        event
+                            .paths
+                            .into_iter()
+                            .filter(|path| !path.starts_with(&target_dir)),
+                    ),
+                    Ok(_) => {}
+                    Err(error) => tracing::warn!("{error}"),
+                }
+            }
+
+            match bumblebee.update_files(&paths) {
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(error) => tracing::error!("{error:#}"),
+            }
+        }
+    }
+}
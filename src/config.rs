@@ -0,0 +1,372 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// Name of the configuration file discovered at the project root
+pub const CONFIG_FILE_NAME: &str = ".bumblebee.toml";
+
+/// How the impacted areas are reported once the analysis is done
+#[derive(Deserialize, ValueEnum, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// Write the impacted snippets of every file under the target directory
+    #[default]
+    Files,
+    /// Print the impacted snippets as JSON on stdout
+    Json,
+}
+
+/// How much code around the impacted references is reported
+#[derive(Deserialize, ValueEnum, Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum Granularity {
+    /// The innermost statement enclosing the reference
+    Statement,
+    /// The innermost function or method, or else the top-level statement
+    Function,
+    /// The top-level statement or declaration
+    #[default]
+    TopLevel,
+    /// The whole file
+    File,
+}
+
+/// How code uses the symbol it references
+#[derive(
+    Serialize, Deserialize, ValueEnum, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReferenceKind {
+    /// Calls it, e.g. `call()` or `new Call()`
+    Call,
+    /// Reads it any other way, e.g. `const copy = value`
+    Read,
+    /// Assigns or updates it, e.g. `value = 1` or `order.total += 1`
+    Write,
+    /// Exports it, e.g. `export { value }` or `export default value`
+    Export,
+    /// `typeof value`
+    Typeof,
+    /// Uses it as a TypeScript type only, e.g. `let order: Order`
+    Type,
+    /// Passes it to a function, e.g. `items.map(format)`
+    Argument,
+}
+
+/// Whether a call sends an event or subscribes a handler to it
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventRole {
+    Emit,
+    Listen,
+}
+
+/// A call that sends or listens to events, e.g. `emit` of `bus.emit("order:paid", order)`
+///
+/// The event key is the first argument of the call, a handler of a listener the second.
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(deny_unknown_fields)]
+pub struct EventPattern {
+    /// Name of the called function or method
+    pub call: String,
+    pub role: EventRole,
+    /// Key of every call, e.g. `message` for `postMessage`, instead of the first argument
+    pub key: Option<String>,
+}
+
+impl EventPattern {
+    fn new(call: &str, role: EventRole, key: Option<&str>) -> Self {
+        Self {
+            call: call.into(),
+            role,
+            key: key.map(String::from),
+        }
+    }
+}
+
+/// Where the analysis starts from
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum QueryConfig {
+    /// A symbol of our code, e.g. `fellow` of `./pkgs/utils/index.js`
+    Symbol { symbol: String, path: PathBuf },
+    /// A symbol of a third-party package, e.g. `debounce` of `lodash`
+    Package { package: String, symbol: String },
+    /// A global without declaration, e.g. `fetch` or `window.localStorage.setItem`
+    Global { global: String },
+    /// An environment variable read through `process.env` or `import.meta.env`
+    Env { env: String },
+    /// A string constant, e.g. a feature flag, a translation key or an action type
+    String { string: String },
+}
+
+/// Project configuration, read from `.bumblebee.toml` and overridden by CLI flags
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// Globs of files to analyze, relative to the project root; everything when empty
+    pub include: Vec<String>,
+    /// Globs of files to skip, relative to the project root
+    pub exclude: Vec<String>,
+    /// Packages of `node_modules` that are analyzed like our own code, e.g. vendored ones
+    pub scan_packages: Vec<String>,
+    /// File extensions (without the leading dot) that are parsed and resolved
+    pub extensions: Vec<String>,
+    /// Import aliases, e.g. `"@app" = ["./src/app"]`
+    pub aliases: BTreeMap<String, Vec<String>>,
+    /// Export conditions used while resolving package imports
+    pub conditions: Vec<String>,
+    /// Files whose impact is summarized at the end of the run, e.g. pages or routes
+    pub entry_points: Vec<PathBuf>,
+    pub output_format: OutputFormat,
+    pub granularity: Granularity,
+    /// Directory the impacted snippets are written to
+    pub target_path: Option<PathBuf>,
+    /// How many hops the references are followed from the queried symbols
+    pub max_depth: Option<usize>,
+    /// Globs of files whose references are reported but not followed any further
+    pub stop_boundaries: Vec<String>,
+    /// Kinds of references the impact spreads through; all of them when empty
+    pub follow: Vec<ReferenceKind>,
+    /// Kinds of references that are reported but not followed any further
+    pub ignore: Vec<ReferenceKind>,
+    /// Calls linking the code emitting an event to the handlers listening to it
+    pub events: Vec<EventPattern>,
+    pub queries: Vec<QueryConfig>,
+    /// Whether the indexes of the files are cached between runs
+    pub cache: bool,
+    /// Directory the indexes are cached in, relative to the project root, see
+    /// [`Config::cache_dir`]
+    pub cache_dir: Option<PathBuf>,
+    /// Whether only the files mentioning what the analysis looks for are parsed
+    pub prefilter: bool,
+    /// Whether the run fails when files couldn't be analyzed completely, e.g. because
+    /// of syntax errors
+    pub strict: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            scan_packages: Vec::new(),
+            extensions: vec!["js".into()],
+            aliases: BTreeMap::new(),
+            conditions: vec!["node".into(), "import".into(), "require".into()],
+            entry_points: Vec::new(),
+            output_format: OutputFormat::default(),
+            granularity: Granularity::default(),
+            target_path: None,
+            max_depth: None,
+            stop_boundaries: Vec::new(),
+            follow: Vec::new(),
+            ignore: Vec::new(),
+            events: vec![
+                EventPattern::new("emit", EventRole::Emit, None),
+                EventPattern::new("dispatchEvent", EventRole::Emit, None),
+                EventPattern::new("postMessage", EventRole::Emit, Some("message")),
+                EventPattern::new("on", EventRole::Listen, None),
+                EventPattern::new("once", EventRole::Listen, None),
+                EventPattern::new("addListener", EventRole::Listen, None),
+                EventPattern::new("addEventListener", EventRole::Listen, None),
+            ],
+            queries: Vec::new(),
+            cache: true,
+            cache_dir: None,
+            prefilter: true,
+            strict: false,
+        }
+    }
+}
+
+impl Config {
+    /// Reads the config from `config_path`, or from `.bumblebee.toml` in `root_path`
+    /// when no path is given. A missing discovered file falls back to the defaults.
+    pub fn load(root_path: &Path, config_path: Option<&Path>) -> Result<Self> {
+        let config_path = match config_path {
+            Some(config_path) => config_path.to_path_buf(),
+            None => {
+                let discovered = root_path.join(CONFIG_FILE_NAME);
+
+                if !discovered.exists() {
+                    return Ok(Self::default());
+                }
+
+                discovered
+            }
+        };
+
+        let contents = std::fs::read_to_string(&config_path)
+            .with_context(|| format!("Unable to read {}", config_path.display()))?;
+
+        Self::parse(&contents).with_context(|| format!("Invalid config {}", config_path.display()))
+    }
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    pub fn has_extension(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| self.extensions.iter().any(|ext| ext == extension))
+    }
+
+    /// Whether the impact spreads through the references of `kind`
+    pub fn follows(&self, kind: ReferenceKind) -> bool {
+        (self.follow.is_empty() || self.follow.contains(&kind)) && !self.ignore.contains(&kind)
+    }
+
+    /// Directory the indexes of the project at `root_path` are cached in. Unless one is
+    /// configured, it's the cache directory of the user, so nothing is written into the
+    /// project; the entries of every project can live side by side there.
+    pub fn cache_dir(&self, root_path: &Path) -> PathBuf {
+        if let Some(cache_dir) = &self.cache_dir {
+            return root_path.join(cache_dir);
+        }
+
+        let user_cache_dir = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+            .unwrap_or_else(std::env::temp_dir);
+
+        user_cache_dir.join("bumblebee")
+    }
+
+    pub fn stop_boundary_set(&self) -> Result<GlobSet> {
+        let mut builder = GlobSetBuilder::new();
+
+        for pattern in &self.stop_boundaries {
+            builder.add(Glob::new(pattern)?);
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_config() {
+        let config = Config::parse(
+            r#"
+            exclude = ["**/*.stories.js"]
+            extensions = ["js", "jsx"]
+            max-depth = 3
+            output-format = "json"
+
+            [aliases]
+            "@app" = ["./src/app"]
+
+            [[queries]]
+            symbol = "fellow"
+            path = "./pkgs/utils/index.js"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.extensions, ["js", "jsx"]);
+        assert_eq!(config.max_depth, Some(3));
+        assert_eq!(config.output_format, OutputFormat::Json);
+        assert_eq!(config.aliases["@app"], ["./src/app"]);
+        assert_eq!(
+            config.queries[0],
+            QueryConfig::Symbol {
+                symbol: "fellow".into(),
+                path: "./pkgs/utils/index.js".into()
+            }
+        );
+        assert_eq!(config.conditions, ["node", "import", "require"]);
+        assert!(config.has_extension(Path::new("index.jsx")));
+        assert!(Config::parse("unknown = 1").is_err());
+    }
+
+    #[test]
+    fn parse_queries() {
+        let config = Config::parse(
+            r#"
+            [[queries]]
+            package = "lodash"
+            symbol = "debounce"
+
+            [[queries]]
+            global = "window.localStorage.setItem"
+
+            [[queries]]
+            env = "API_URL"
+
+            [[queries]]
+            string = "checkout.v2"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.queries,
+            [
+                QueryConfig::Package {
+                    package: "lodash".into(),
+                    symbol: "debounce".into()
+                },
+                QueryConfig::Global {
+                    global: "window.localStorage.setItem".into()
+                },
+                QueryConfig::Env {
+                    env: "API_URL".into()
+                },
+                QueryConfig::String {
+                    string: "checkout.v2".into()
+                },
+            ]
+        );
+        // a query mixing the keys of two forms is ambiguous
+        assert!(
+            Config::parse("[[queries]]\nsymbol = \"x\"\npath = \"a.js\"\nenv = \"X\"").is_err()
+        );
+        assert!(Config::parse("[[queries]]\nsymbol = \"x\"").is_err());
+    }
+
+    #[test]
+    fn follows() {
+        let mut config = Config::default();
+        assert!(config.follows(ReferenceKind::Type));
+
+        config.ignore = vec![ReferenceKind::Type];
+        assert!(config.follows(ReferenceKind::Call));
+        assert!(!config.follows(ReferenceKind::Type));
+
+        config.follow = vec![ReferenceKind::Call, ReferenceKind::Type];
+        assert!(config.follows(ReferenceKind::Call));
+        assert!(!config.follows(ReferenceKind::Read));
+        // ignoring wins over following
+        assert!(!config.follows(ReferenceKind::Type));
+    }
+
+    #[test]
+    fn stop_boundary_set() {
+        let config = Config {
+            stop_boundaries: vec!["src/legacy/**".into(), "*.test.js".into()],
+            ..Default::default()
+        };
+        let stop_boundaries = config.stop_boundary_set().unwrap();
+
+        assert!(stop_boundaries.is_match("src/legacy/cart.js"));
+        assert!(stop_boundaries.is_match("cart.test.js"));
+        assert!(!stop_boundaries.is_match("src/cart.js"));
+
+        let config = Config {
+            stop_boundaries: vec!["src/[".into()],
+            ..Default::default()
+        };
+        assert!(config.stop_boundary_set().is_err());
+    }
+}
@@ -1,227 +1,1542 @@
-use crate::query::Query;
+use crate::cache::IndexCache;
+use crate::config::Config;
+use crate::config::EventRole;
+use crate::diagnostics::{Diagnostic, Diagnostics};
+use crate::error::BumblebeeError;
+use crate::graph::ImportGraph;
+use crate::index::EventSite;
+use crate::linker::{Linker, ModuleInterface, SymbolKey};
+use crate::prefilter::{module_names, Prefilter};
+use crate::query::{
+    DiffQuery, EnvQuery, GlobalQuery, PackageQuery, Query, StringQuery, SymbolQuery,
+};
+use crate::report::{FileDiagnostic, ImpactReport, ImpactedUnit};
+use crate::resolver::ModuleResolver;
 use crate::service::Service;
 use crate::service::ServiceReference;
 use anyhow::Result;
 use dunce::realpath;
-use ignore::Walk;
-use oxc_allocator::Allocator;
-use oxc_parser::{Parser, ParserReturn};
-use oxc_semantic::{NodeId, SemanticBuilder, SemanticBuilderReturn};
-use oxc_span::{GetSpan, SourceType};
+use globset::GlobSet;
+use ignore::{overrides::OverrideBuilder, WalkBuilder};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::path::{Component, PathBuf};
 use std::{
     collections::{HashMap, HashSet},
-    fs::File,
-    io::Write,
-    mem::ManuallyDrop,
     path::Path,
 };
-use std::{ffi::OsStr, path::PathBuf};
+
+/// A link between two pieces of code that isn't a reference, e.g. an emitted event and
+/// its handler. Heuristic edges are guessed from names rather than resolved.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Edge {
+    pub kind: &'static str,
+    pub key: String,
+    /// `path:line` of the source, e.g. the `emit` call
+    pub from: String,
+    /// `path:line` of the target, e.g. the `on` call subscribing the handler
+    pub to: String,
+    pub heuristic: bool,
+}
 
 /// The main Bumblebee struct that handles code analysis
-pub struct Bumblebee<'a> {
-    root_path: &'a Path,
-    target_dir: &'a Path,
-    allocator: &'a Allocator,
-    queries: HashSet<Query>,
-    services: HashMap<PathBuf, &'a mut ServiceReference<'a>>,
+///
+/// It owns the index of the project, which is kept up to date with `update_files` and
+/// can be analyzed any number of times.
+///
+/// ```no_run
+/// use bumblebee::core::Bumblebee;
+/// use bumblebee::query::{Query, SymbolQuery};
+///
+/// let mut bumblebee = Bumblebee::builder().root("./app").build()?;
+/// let query = SymbolQuery::new_with_symbol("call".into(), "src/factory.js".into());
+/// let report = bumblebee.analyze(&[Query::Symbol(query)])?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct Bumblebee {
+    root_path: PathBuf,
+    config: Config,
+    stop_boundaries: GlobSet,
+    symbol_queries: Vec<SymbolQuery>,
+    /// Symbols whose references have been looked for
+    queries: HashSet<SymbolKey>,
+    package_queries: Vec<PackageQuery>,
+    global_queries: Vec<GlobalQuery>,
+    env_queries: Vec<EnvQuery>,
+    string_queries: Vec<StringQuery>,
+    diff_query: Option<DiffQuery>,
+    services: HashMap<PathBuf, ServiceReference>,
+    /// Files of the project not indexed yet
+    prefilter: Prefilter,
+    /// Problems of the files, reported without stopping the analysis
+    diagnostics: Diagnostics,
+    resolver: ModuleResolver,
+    /// Indexes of the previous runs, `None` when caching is turned off
+    cache: Option<IndexCache>,
+    /// Imports and exports of every service, extracted while the files are parsed
+    interfaces: HashMap<PathBuf, ModuleInterface>,
+    linker: Linker,
+    import_graph: ImportGraph,
+    /// Event edges, with the emit and listen sites they're ordered by in the report
+    edges: Vec<((String, usize, String, usize), Edge)>,
+}
+
+/// Builds a [`Bumblebee`] for a project, see [`Bumblebee::builder`]
+#[derive(Default)]
+pub struct BumblebeeBuilder {
+    root_path: Option<PathBuf>,
+    config: Option<Config>,
 }
 
-impl<'a> Bumblebee<'a> {
-    /// Creates a new Bumblebee instance
-    pub fn new(root_path: &'a Path, target_dir: &'a Path, allocator: &'a mut Allocator) -> Self {
-        let path_buf = realpath(root_path).expect("Invalid project path");
-        let path = &**allocator.alloc(ManuallyDrop::new(path_buf));
+impl BumblebeeBuilder {
+    /// Directory of the project to analyze, required
+    pub fn root(mut self, root_path: impl Into<PathBuf>) -> Self {
+        self.root_path = Some(root_path.into());
+        self
+    }
+
+    /// Config of the analysis, `.bumblebee.toml` of the project by default
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Scans the project for its files, indexing only the ones the prefilter can't
+    /// skip
+    pub fn build(self) -> Result<Bumblebee> {
+        let root_path = self.root_path.ok_or(BumblebeeError::MissingRoot)?;
+        let config = match self.config {
+            Some(config) => config,
+            None => Config::load(&root_path, None)?,
+        };
+
+        let mut bumblebee = Bumblebee::new(&root_path, config)?;
+        bumblebee.update_services()?;
 
-        Self {
-            root_path: path.as_path(),
-            target_dir,
-            allocator,
+        Ok(bumblebee)
+    }
+}
+
+impl Bumblebee {
+    pub fn builder() -> BumblebeeBuilder {
+        BumblebeeBuilder::default()
+    }
+
+    fn new(root_path: &Path, config: Config) -> Result<Self> {
+        let root_path = realpath(root_path).map_err(|source| BumblebeeError::ProjectPath {
+            path: root_path.to_path_buf(),
+            source,
+        })?;
+
+        Ok(Self {
+            stop_boundaries: config.stop_boundary_set()?,
+            resolver: ModuleResolver::new(&root_path, &config),
+            cache: config
+                .cache
+                .then(|| IndexCache::new(config.cache_dir(&root_path), &root_path, &config)),
+            root_path,
+            config,
+            symbol_queries: Default::default(),
             queries: Default::default(),
+            package_queries: Default::default(),
+            global_queries: Default::default(),
+            env_queries: Default::default(),
+            string_queries: Default::default(),
+            diff_query: Default::default(),
             services: Default::default(),
+            prefilter: Default::default(),
+            diagnostics: Default::default(),
+            interfaces: Default::default(),
+            linker: Default::default(),
+            import_graph: Default::default(),
+            edges: Default::default(),
+        })
+    }
+
+    pub fn root_path(&self) -> &Path {
+        &self.root_path
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Problems of the indexed files, e.g. imports that don't resolve
+    pub fn diagnostics(&self) -> &Diagnostics {
+        &self.diagnostics
+    }
+
+    /// Files whose analysis may miss impacted code: the ones with syntax errors and the
+    /// ones that couldn't be indexed at all
+    fn incomplete_files(&self) -> Vec<&PathBuf> {
+        self.diagnostics
+            .paths()
+            .filter(|source_path| {
+                self.services
+                    .get(*source_path)
+                    .is_none_or(|service_reference| service_reference.service().index().incomplete)
+            })
+            .collect()
+    }
+
+    /// Adds a query for a symbol of a third-party package, resolved against the imports
+    /// of our code once the services are up to date
+    fn evaluate_package_query(&mut self, query: PackageQuery) {
+        // resolved like an import of a file at the root of the project
+        let entry = self
+            .resolver
+            .resolve(&self.root_path.join("package.json"), query.package())
+            .ok();
+
+        self.package_queries.push(query.with_entry(entry));
+    }
+
+    /// Adds a query for a global without declaration, e.g. `fetch` or `process.env`
+    fn evaluate_global_query(&mut self, query: GlobalQuery) {
+        self.global_queries.push(query);
+    }
+
+    /// Adds a query for the reads of an environment variable
+    fn evaluate_env_query(&mut self, query: EnvQuery) {
+        self.env_queries.push(query);
+    }
+
+    /// Adds a query for the uses of a string constant, e.g. a feature flag name
+    fn evaluate_string_query(&mut self, query: StringQuery) {
+        self.string_queries.push(query);
+    }
+
+    /// Adds a query for the code changed since the last commit, replacing the previous one
+    fn evaluate_diff_query(&mut self, query: DiffQuery) {
+        self.diff_query = Some(query);
+    }
+
+    /// Finds the impacted areas of `queries`, forgetting the ones of the previous
+    /// analysis
+    pub fn analyze(&mut self, queries: &[Query]) -> Result<ImpactReport> {
+        self.clear_queries();
+
+        for query in queries {
+            tracing::debug!(?query, "evaluating query");
+
+            match query.clone() {
+                Query::Symbol(query) => self.evaluate_query(query)?,
+                Query::Package(query) => self.evaluate_package_query(query),
+                Query::Global(query) => self.evaluate_global_query(query),
+                Query::Env(query) => self.evaluate_env_query(query),
+                Query::String(query) => self.evaluate_string_query(query),
+                Query::Diff(query) => self.evaluate_diff_query(query),
+            }
         }
+
+        self.find_references_recursively()?;
+        Ok(self.report())
     }
 
-    /// Evaluates a query to find references to a symbol
-    pub fn evaluate_query(&mut self, query: Query) {
-        let source_path =
-            realpath(self.root_path.join(query.symbol_path())).expect("Invalid query source path!");
-        let source_text = std::fs::read_to_string(&source_path).unwrap();
-        let source_type = SourceType::from_path(&source_path).unwrap();
-        let source_text_ref = self.allocator.alloc_str(&source_text);
+    /// Adds a query for the references to a symbol of our code, indexing its file right
+    /// away
+    fn evaluate_query(&mut self, query: SymbolQuery) -> Result<()> {
+        let query_path = self.root_path.join(query.symbol_path());
+        let source_path = realpath(&query_path).map_err(|source| BumblebeeError::QueryPath {
+            path: query_path,
+            source,
+        })?;
 
-        let ParserReturn { program, .. } = &**self.allocator.alloc(ManuallyDrop::new(
-            Parser::new(self.allocator, source_text_ref, source_type).parse(),
-        ));
+        if !self.services.contains_key(&source_path) {
+            self.prefilter.remove(&source_path);
+            self.index_file(source_path.clone());
+            self.link(&[source_path]);
+        }
 
-        let SemanticBuilderReturn { semantic, .. } = SemanticBuilder::new().build(program);
-        let service = &**self.allocator.alloc(ManuallyDrop::new(
-            Service::build(self.root_path.into(), source_path.to_owned(), semantic).unwrap(),
-        ));
-        let reference_node_ids = &mut **self.allocator.alloc(ManuallyDrop::new(HashSet::new()));
-        let reference_symbol_ids = &mut **self.allocator.alloc(ManuallyDrop::new(HashSet::new()));
-        let symbol_id = service.get_symbol_id(query.symbol());
+        self.symbol_queries.push(query);
 
-        if let Some(symbol_id) = symbol_id {
-            let symbol_name = service.semantic().scoping().symbol_name(symbol_id);
+        Ok(())
+    }
 
-            self.queries.insert(Query::new(
-                symbol_id,
-                symbol_name.into(),
-                query.symbol_path().to_path_buf(),
-            ));
+    /// The symbol a query is about, looked up again on every analysis since its file may
+    /// have changed in between
+    fn query_symbol(&self, query: &SymbolQuery) -> Option<SymbolKey> {
+        let source_path = realpath(self.root_path.join(query.symbol_path())).ok()?;
+        let symbol_id = self
+            .services
+            .get(&source_path)?
+            .service()
+            .get_symbol_id(query.symbol())?;
+
+        Some(SymbolKey::new(source_path, symbol_id))
+    }
+
+    /// Indexes the file at `source_path` on its own
+    fn index_file(&mut self, source_path: PathBuf) {
+        let service = Service::build(
+            self.root_path.clone(),
+            source_path.to_owned(),
+            &self.config,
+            &self.resolver,
+            self.cache.as_ref(),
+        );
+
+        self.add_service(source_path, service);
+    }
+
+    /// Adds a built service, replacing the previous one of its file, or records why the
+    /// file couldn't be indexed
+    fn add_service(&mut self, source_path: PathBuf, service: Result<Service, BumblebeeError>) {
+        let service = match service {
+            Ok(service) => service,
+            Err(error) => {
+                self.diagnostics
+                    .set(&source_path, vec![Diagnostic::new(None, &error)]);
+                self.services.remove(&source_path);
+                self.interfaces.remove(&source_path);
+                return;
+            }
+        };
+
+        let mut diagnostics = service.index().diagnostics.clone();
+        let mut specifiers = HashSet::new();
+
+        for import in &service.module_interface().imports {
+            if import.source.is_none() && specifiers.insert(&import.specifier) {
+                let unresolved = self.resolver.unresolved(&source_path, &import.specifier);
+                diagnostics.extend(unresolved.map(|error| Diagnostic::new(None, &error)));
+            }
         }
 
-        let service_reference =
-            &mut **self
-                .allocator
-                .alloc(ManuallyDrop::new(ServiceReference::new(
-                    service,
-                    reference_node_ids,
-                    reference_symbol_ids,
-                )));
+        self.diagnostics.set(&source_path, diagnostics);
+        self.interfaces
+            .insert(source_path.to_owned(), service.module_interface().clone());
+        self.services
+            .insert(source_path, ServiceReference::new(service));
+    }
+
+    /// Files to analyze: everything under the root that passes the include/exclude
+    /// globs and the ignore files, plus the opted-in packages of `node_modules`
+    ///
+    /// With `only`, just the directories leading to those paths are walked, so they're
+    /// checked against the same globs and ignore files without scanning the whole tree.
+    fn source_paths(&self, only: Option<&[PathBuf]>) -> Result<Vec<PathBuf>> {
+        let mut overrides = OverrideBuilder::new(&self.root_path);
+        let mut package_overrides = OverrideBuilder::new(&self.root_path);
+
+        for pattern in &self.config.include {
+            overrides.add(pattern)?;
+        }
+
+        for pattern in &self.config.exclude {
+            overrides.add(&format!("!{pattern}"))?;
+            package_overrides.add(&format!("!{pattern}"))?;
+        }
+
+        let walk = |path: &Path| {
+            let mut builder = WalkBuilder::new(path);
+
+            if let Some(only) = only {
+                let only = only.to_vec();
+                builder.filter_entry(move |entry| {
+                    only.iter().any(|path| path.starts_with(entry.path()))
+                });
+            }
+
+            builder
+        };
+
+        let mut walks = vec![walk(&self.root_path).overrides(overrides.build()?).build()];
+
+        // node_modules is usually git ignored, so the packages we vendor are walked on
+        // their own without the ignore files; include globs only apply to our own code
+        let package_overrides = package_overrides.build()?;
+
+        for package in &self.config.scan_packages {
+            let package_path = self.root_path.join("node_modules").join(package);
+
+            if !package_path.is_dir() {
+                return Err(BumblebeeError::PackageNotInstalled(package.to_owned()).into());
+            }
+
+            walks.push(
+                walk(&package_path)
+                    .standard_filters(false)
+                    .hidden(true)
+                    .overrides(package_overrides.clone())
+                    .build(),
+            );
+        }
 
-        self.services.insert(source_path.clone(), service_reference);
+        Ok(walks
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| self.config.has_extension(entry.path()))
+            .map(|entry| entry.into_path())
+            .collect())
     }
 
     /// Updates the services by scanning the root directory for JavaScript files
-    pub fn update_services(&mut self) -> Result<()> {
-        for entry in Walk::new(self.root_path).flatten() {
-            if entry.path().extension() == Some(OsStr::new("js")) {
-                let reference_node_ids =
-                    &mut **self.allocator.alloc(ManuallyDrop::new(HashSet::new()));
-                let reference_symbol_ids =
-                    &mut **self.allocator.alloc(ManuallyDrop::new(HashSet::new()));
-                let source_path =
-                    realpath(self.root_path.join(entry.path())).expect("Invalid source path!");
-
-                if self.services.get_mut(&source_path).is_none() {
-                    let source_text = std::fs::read_to_string(&source_path)?;
-                    let source_type = SourceType::from_path(&source_path)?;
-                    let source_text_ref = self.allocator.alloc_str(&source_text);
-
-                    let parser_return = self.allocator.alloc(ManuallyDrop::new(
-                        Parser::new(self.allocator, source_text_ref, source_type).parse(),
-                    ));
+    ///
+    /// Unless the prefilter is turned off, the files are only indexed once their text
+    /// mentions what the analysis looks for, see `find_references_recursively`.
+    fn update_services(&mut self) -> Result<()> {
+        let source_paths: Vec<PathBuf> = self
+            .source_paths(None)?
+            .into_iter()
+            .filter_map(|path| realpath(self.root_path.join(path)).ok())
+            .filter(|source_path| !self.services.contains_key(source_path))
+            .collect();
+        let source_paths = if self.config.prefilter {
+            self.prefilter.extend(source_paths)?
+        } else {
+            source_paths
+        };
+
+        self.index_files(&source_paths);
+        self.link(&source_paths);
+
+        Ok(())
+    }
+
+    /// Indexes the files at `source_paths`, without linking them
+    ///
+    /// Every file is parsed into an arena of its own, so the files are parsed and
+    /// analyzed on all cores and only merged into the services map afterwards. Files
+    /// whose index is cached and up to date aren't parsed at all.
+    fn index_files(&mut self, source_paths: &[PathBuf]) {
+        let root_path = &self.root_path;
+        let (config, resolver) = (&self.config, &self.resolver);
+        let cache = self.cache.as_ref();
+
+        let services: Vec<_> = source_paths
+            .par_iter()
+            .map(|source_path| {
+                let service = Service::build(
+                    root_path.clone(),
+                    source_path.clone(),
+                    config,
+                    resolver,
+                    cache,
+                );
+
+                (source_path.clone(), service)
+            })
+            .collect();
+
+        for (source_path, service) in services {
+            self.add_service(source_path, service);
+        }
+    }
+
+    /// Indexes and links the pending files mentioning any of `patterns`. Returns whether
+    /// there was any.
+    fn index_mentioning(&mut self, patterns: impl IntoIterator<Item = String>) -> Result<bool> {
+        let source_paths = self.prefilter.take_matching(patterns)?;
+
+        if source_paths.is_empty() {
+            return Ok(false);
+        }
+
+        self.index_files(&source_paths);
+        self.link(&source_paths);
+
+        Ok(true)
+    }
+
+    /// Indexes the pending files that can reference the symbols of `queries`: the ones
+    /// mentioning their names, or a module they can be imported from. Returns whether
+    /// there was any.
+    fn index_dependents(&mut self, queries: &[(SymbolKey, usize)]) -> Result<bool> {
+        let mut is_indexed = false;
 
-                    let SemanticBuilderReturn { semantic, .. } =
-                        SemanticBuilder::new().build(&parser_return.program);
-
-                    let service = &**self.allocator.alloc(ManuallyDrop::new(Service::build(
-                        self.root_path.into(),
-                        source_path.to_owned(),
-                        semantic,
-                    )?));
-
-                    let service_reference =
-                        &mut **self
-                            .allocator
-                            .alloc(ManuallyDrop::new(ServiceReference::new(
-                                service,
-                                reference_node_ids,
-                                reference_symbol_ids,
-                            )));
-
-                    self.services
-                        .insert(source_path.to_owned(), service_reference);
+        loop {
+            let mut patterns = Vec::new();
+
+            for (query, _) in queries {
+                if let Some(service_reference) = self.services.get(query.file()) {
+                    let symbol = service_reference
+                        .service()
+                        .index()
+                        .symbol(query.symbol_id());
+                    patterns.push(symbol.name.to_owned());
+                }
+
+                patterns.extend(self.linker.export_names(query).iter().cloned());
+
+                // the files re-exporting the symbol are indexed on the way, and can be
+                // imported from in turn
+                let files = std::iter::once(query.file().to_path_buf())
+                    .chain(self.import_graph.re_exporters(query.file()));
+
+                for file in files {
+                    patterns.extend(module_names(&self.root_path, &self.config, &file));
                 }
             }
+
+            if !self.index_mentioning(patterns)? {
+                return Ok(is_indexed);
+            }
+
+            is_indexed = true;
         }
+    }
 
-        Ok(())
+    /// Names the code impacted by the package, global, environment and string queries
+    /// mentions
+    fn seed_patterns(&self) -> Vec<String> {
+        // aliases of the package are imported without its name
+        let packages = self.package_queries.iter().flat_map(|query| {
+            let package = query.package();
+            let entry_names = query
+                .entry()
+                .map(|entry| module_names(&self.root_path, &self.config, entry));
+
+            std::iter::once(package.strip_prefix("node:").unwrap_or(package).to_owned())
+                .chain(entry_names.into_iter().flatten())
+        });
+        let globals = self
+            .global_queries
+            .iter()
+            .filter_map(|query| query.members().first().cloned());
+        let envs = self.env_queries.iter().map(|query| query.name().to_owned());
+        let strings = self
+            .string_queries
+            .iter()
+            .map(|query| query.value().to_owned());
+
+        packages.chain(globals).chain(envs).chain(strings).collect()
+    }
+
+    /// Indexes the changed `paths` again, dropping the ones that were deleted, and links
+    /// them again. Returns whether any of them is a file of the project.
+    pub fn update_files(&mut self, paths: &[PathBuf]) -> Result<bool> {
+        let source_paths: HashSet<PathBuf> = self
+            .source_paths(Some(paths))?
+            .into_iter()
+            .filter_map(|path| realpath(self.root_path.join(path)).ok())
+            .collect();
+        let mut updated_paths = Vec::new();
+
+        // files may have been added or deleted, changing what the specifiers resolve to
+        if !paths.is_empty() {
+            self.resolver.clear();
+        }
+
+        for path in paths {
+            let source_path = realpath(path).unwrap_or_else(|_| path.to_owned());
+            // the file of a symbol query is indexed even when it isn't included
+            let is_source =
+                source_paths.contains(&source_path) || self.services.contains_key(&source_path);
+
+            if is_source && source_path.is_file() {
+                self.prefilter.remove(&source_path);
+                self.index_file(source_path.clone());
+                updated_paths.push(source_path);
+            } else if self.services.remove(&source_path).is_some() {
+                self.interfaces.remove(&source_path);
+                self.diagnostics.remove(&source_path);
+                updated_paths.push(source_path);
+            } else {
+                // a file that was never indexed doesn't change the analysis
+                self.prefilter.remove(&source_path);
+            }
+        }
+
+        self.link(&updated_paths);
+
+        Ok(!updated_paths.is_empty())
+    }
+
+    /// Forgets the queries, and the impacted code found for them
+    fn clear_queries(&mut self) {
+        self.symbol_queries.clear();
+        self.package_queries.clear();
+        self.global_queries.clear();
+        self.env_queries.clear();
+        self.string_queries.clear();
+        self.diff_query = None;
+        self.reset();
+    }
+
+    /// Forgets the impacted code and the edges found by the previous analysis, keeping
+    /// the queries and the indexes of the files
+    fn reset(&mut self) {
+        self.queries.clear();
+        self.edges.clear();
+
+        for service_reference in self.services.values_mut() {
+            service_reference.clear();
+        }
+    }
+
+    /// Binds the imports of the indexed, changed or deleted files at `source_paths` to
+    /// the symbols they import, and records which files they import
+    ///
+    /// The files importing them, directly or through re-exports, are bound again too,
+    /// since what their imports resolve to may have been added, moved or removed.
+    fn link(&mut self, source_paths: &[PathBuf]) {
+        self.import_graph.update(&self.interfaces, source_paths);
+
+        let files = self
+            .import_graph
+            .dependents(source_paths.iter().map(PathBuf::as_path));
+
+        self.linker.update(&self.interfaces, &files);
     }
 
     /// Recursively finds all references to the queried symbols
-    pub fn find_references_recursively(&mut self) {
-        let mut queries: Vec<Query> = self.queries.iter().cloned().collect();
-        let queries_original_len = queries.len();
-        let mut queries_len = queries_original_len;
+    ///
+    /// References are followed at most `max_depth` hops away from the queried symbols
+    /// and never out of the files matching a stop boundary. The files not indexed yet
+    /// are indexed on the way, once their text can reference the impacted symbols.
+    fn find_references_recursively(&mut self) -> Result<()> {
+        let mut queries: Vec<(SymbolKey, usize)> = Vec::new();
         let mut i = 0;
 
-        // Using a while loop instead of iterator to handle the dynamic growth of queries
-        while i < queries_len {
-            self.services
-                .iter_mut()
-                .map(|(source_path, service_reference)| {
-                    let scoping = service_reference.service().semantic().scoping();
-                    (source_path, service_reference, scoping)
-                })
-                .for_each(|(source_path, service_reference, scoping)| {
-                    let query = &queries[i];
-                    service_reference.find_references(query);
-
-                    let symbol_ids = service_reference.reference_symbol_ids();
-
-                    queries.extend(symbol_ids.iter().filter_map(|symbol_id| {
-                        let symbol_name = scoping.symbol_name(*symbol_id);
-                        let query = Query::new(
-                            *symbol_id,
-                            symbol_name.to_owned(),
-                            source_path.to_path_buf(),
-                        );
+        for query in &self.symbol_queries {
+            if let Some(symbol) = self.query_symbol(query) {
+                if self.queries.insert(symbol.clone()) {
+                    queries.push((symbol, 0));
+                }
+            }
+        }
 
-                        if !self.queries.contains(&query) {
-                            return Some(query);
-                        }
+        self.index_mentioning(self.seed_patterns())?;
 
-                        None
-                    }));
-                    queries_len = queries.len();
-                    println!("IN: {}", queries.len());
-                });
+        let changed_paths: Vec<PathBuf> = self
+            .diff_query
+            .iter()
+            .flat_map(DiffQuery::paths)
+            .filter_map(|path| realpath(self.root_path.join(path)).ok())
+            .collect();
+        let changed_paths: Vec<PathBuf> = changed_paths
+            .into_iter()
+            .filter(|source_path| self.prefilter.remove(source_path))
+            .collect();
+
+        if !changed_paths.is_empty() {
+            self.index_files(&changed_paths);
+            self.link(&changed_paths);
+        }
+
+        // the imports of the queried packages and the uses of the queried globals,
+        // environment variables and strings are where their impact enters our code
+        for (source_path, service_reference) in self.services.iter_mut() {
+            for package_query in &self.package_queries {
+                service_reference.find_package_references(package_query, &self.config);
+            }
+
+            for global_query in &self.global_queries {
+                service_reference.find_global_references(global_query, &self.config);
+            }
 
-            if i >= queries_original_len {
-                self.queries.insert(queries[i].clone());
+            for env_query in &self.env_queries {
+                service_reference.find_env_references(env_query, &self.config);
             }
 
-            i += 1;
+            for string_query in &self.string_queries {
+                service_reference.find_string_references(string_query);
+            }
+
+            if let Some(diff_query) = &self.diff_query {
+                let relative_path = relative_path(&self.root_path, source_path);
+                service_reference.find_changed_references(diff_query.lines(relative_path));
+            }
+
+            queue_impacted_symbols(
+                source_path,
+                service_reference,
+                0,
+                &mut self.queries,
+                &mut queries,
+            );
         }
+
+        let mut linked_emit_sites = HashSet::new();
+        // queries before this one have had the files that can reference them indexed
+        let mut indexed = 0;
+        let mut dependents = HashMap::new();
+
+        loop {
+            // Using a while loop instead of iterator to handle the dynamic growth of queries
+            while i < queries.len() {
+                if i == indexed && self.index_dependents(&queries[i..])? {
+                    dependents.clear();
+                }
+
+                indexed = indexed.max(queries.len());
+
+                let (query, depth) = queries[i].clone();
+                let symbol = self
+                    .services
+                    .get(query.file())
+                    .map_or("", |service_reference| {
+                        &service_reference
+                            .service()
+                            .index()
+                            .symbol(query.symbol_id())
+                            .name
+                    });
+                let _span = tracing::debug_span!(
+                    "query",
+                    file = %relative_path(&self.root_path, query.file()).display(),
+                    symbol,
+                    depth,
+                )
+                .entered();
+                let expand = self
+                    .config
+                    .max_depth
+                    .is_none_or(|max_depth| depth < max_depth);
+
+                // a symbol can only be referenced from its own file and the files importing it
+                let dependents = dependents
+                    .entry(query.file().to_path_buf())
+                    .or_insert_with(|| self.import_graph.dependents([query.file()]));
+
+                for source_path in dependents.iter() {
+                    let Some(service_reference) = self.services.get_mut(source_path) else {
+                        continue;
+                    };
+
+                    let relative_path = relative_path(&self.root_path, source_path);
+                    let _span =
+                        tracing::debug_span!("file", path = %relative_path.display()).entered();
+
+                    service_reference.find_references(&query, depth, &self.linker, &self.config);
+
+                    if !expand || self.stop_boundaries.is_match(relative_path) {
+                        continue;
+                    }
+
+                    queue_impacted_symbols(
+                        source_path,
+                        service_reference,
+                        depth + 1,
+                        &mut self.queries,
+                        &mut queries,
+                    );
+                }
+
+                i += 1;
+            }
+
+            // events emitted by the impacted code reach their handlers in any file, which
+            // can impact more code in turn
+            if !self.link_events(&mut linked_emit_sites, &mut queries)? {
+                break;
+            }
+        }
+
+        Ok(())
     }
 
-    /// Dumps all found references to files in the target directory
-    pub fn dump_reference_files(&'a self) {
-        std::fs::create_dir_all(self.target_dir).ok();
+    /// Marks the handlers of the events emitted by the impacted code as impacted, and
+    /// queues their symbols one hop further than the code emitting the event. Returns
+    /// whether any new event was linked.
+    fn link_events(
+        &mut self,
+        linked_emit_sites: &mut HashSet<(PathBuf, u32)>,
+        queries: &mut Vec<(SymbolKey, usize)>,
+    ) -> Result<bool> {
+        let emit_sites = self.event_sites();
+        let mut emitted = Vec::new();
+
+        for (source_path, sites) in &emit_sites {
+            let relative_path = relative_path(&self.root_path, source_path);
+
+            if self.stop_boundaries.is_match(relative_path) {
+                continue;
+            }
+
+            let service_reference = &self.services[source_path];
+
+            for site in sites {
+                if site.role != EventRole::Emit
+                    || linked_emit_sites.contains(&(source_path.to_owned(), site.span.start))
+                {
+                    continue;
+                }
+
+                if let Some(depth) = service_reference.impact_depth(site.span) {
+                    linked_emit_sites.insert((source_path.to_owned(), site.span.start));
+                    emitted.push((relative_path.display().to_string(), site, depth));
+                }
+            }
+        }
+
+        // a handler is queued as close to the queried symbols as its closest emitter
+        emitted.sort_by_key(|(_, _, depth)| *depth);
+
+        // the handlers are in the files mentioning the emitted keys
+        let listen_sites;
+        let event_sites =
+            if self.index_mentioning(emitted.iter().map(|(_, site, _)| site.key.clone()))? {
+                listen_sites = self.event_sites();
+                &listen_sites
+            } else {
+                &emit_sites
+            };
+
+        for (source_path, sites) in event_sites {
+            let relative_path = relative_path(&self.root_path, source_path);
+            let service_reference = self.services.get_mut(source_path).unwrap();
+            let is_stopped = self.stop_boundaries.is_match(relative_path);
+
+            for site in sites.iter().filter(|site| site.role == EventRole::Listen) {
+                for (emit_path, emit_site, emit_depth) in &emitted {
+                    if emit_site.key != site.key {
+                        continue;
+                    }
+
+                    service_reference.add_event_handler(site, emit_depth + 1);
+
+                    let listen_path = relative_path.display().to_string();
+                    let position = (
+                        emit_path.to_owned(),
+                        emit_site.line,
+                        listen_path.clone(),
+                        site.line,
+                    );
+
+                    self.edges.push((
+                        position,
+                        Edge {
+                            kind: "event",
+                            key: site.key.clone(),
+                            from: format!("{}:{}", emit_path, emit_site.line),
+                            to: format!("{}:{}", listen_path, site.line),
+                            heuristic: true,
+                        },
+                    ));
+
+                    let expand = self
+                        .config
+                        .max_depth
+                        .is_none_or(|max_depth| *emit_depth < max_depth);
+
+                    if expand && !is_stopped {
+                        queue_impacted_symbols(
+                            source_path,
+                            service_reference,
+                            emit_depth + 1,
+                            &mut self.queries,
+                            queries,
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok(!emitted.is_empty())
+    }
 
+    /// Event sites of every service, by file
+    fn event_sites(&self) -> HashMap<PathBuf, Vec<EventSite>> {
         self.services
             .iter()
-            .for_each(|(source_path, service_reference)| {
-                println!(
-                    "{}: {:?}",
-                    source_path.display(),
-                    service_reference.reference_node_ids()
-                );
-                if !service_reference.reference_node_ids().is_empty() {
-                    let mut reference_node_ids: Vec<NodeId> = service_reference
-                        .reference_node_ids()
+            .map(|(source_path, service_reference)| {
+                let event_sites = service_reference.service().event_sites().to_vec();
+                (source_path.to_owned(), event_sites)
+            })
+            .collect()
+    }
+
+    /// Entry points from the config that contain at least one impacted reference
+    fn impacted_entry_points(&self) -> Vec<PathBuf> {
+        self.config
+            .entry_points
+            .iter()
+            .filter(|entry_point| {
+                realpath(self.root_path.join(entry_point))
+                    .ok()
+                    .and_then(|source_path| self.services.get(&source_path))
+                    .is_some_and(|service_reference| !service_reference.impacted_units().is_empty())
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// The impacted units, edges and diagnostics found by the analysis
+    fn report(&self) -> ImpactReport {
+        let mut units = Vec::new();
+        let mut flow_edges = Vec::new();
+        // files changed since they were indexed, e.g. while watching, are incomplete
+        let mut changed_files = Vec::new();
+
+        for (source_path, service_reference) in &self.services {
+            if service_reference.impacted_units().is_empty() {
+                continue;
+            }
+
+            let service = service_reference.service();
+            let path = relative_path(&self.root_path, source_path);
+
+            // values passed around are only followed within the file, and less surely
+            // than references
+            for (line, receiver) in service_reference.flows() {
+                let position = (path.to_path_buf(), *line, receiver.line);
+
+                flow_edges.push((
+                    position,
+                    Edge {
+                        kind: receiver.flow.name(),
+                        key: service.index().symbol(receiver.symbol_id).name.to_string(),
+                        from: format!("{}:{}", path.display(), line),
+                        to: format!("{}:{}", path.display(), receiver.line),
+                        heuristic: true,
+                    },
+                ));
+            }
+
+            let mut impacted_units: Vec<usize> =
+                service_reference.impacted_units().keys().copied().collect();
+            impacted_units.sort_unstable();
+
+            let texts = service.snippets(&impacted_units).unwrap_or_else(|error| {
+                changed_files.push((path.to_path_buf(), Diagnostic::new(None, &error)));
+                vec![String::new(); impacted_units.len()]
+            });
+
+            for (unit_index, text) in impacted_units.into_iter().zip(texts) {
+                let unit = &service.index().units[unit_index];
+                let id = match unit.name.as_str() {
+                    "" => path.display().to_string(),
+                    name => format!("{}#{}", path.display(), name),
+                };
+
+                units.push(ImpactedUnit {
+                    id,
+                    path: path.to_path_buf(),
+                    start_line: service.index().line(unit.span.start),
+                    end_line: service.index().line(unit.span.end),
+                    reference_kinds: service_reference.impacted_units()[&unit_index]
                         .iter()
                         .copied()
-                        .collect();
-                    reference_node_ids.sort_unstable();
+                        .collect(),
+                    text,
+                });
+            }
+        }
 
-                    let relative_path = source_path.strip_prefix(self.root_path).unwrap();
-                    let target_path = self.target_dir.join(relative_path);
+        // units of a file stay ordered by position
+        units.sort_by(|unit, other| unit.path.cmp(&other.path));
+        flow_edges.sort_by(|(position, _), (other, _)| position.cmp(other));
 
-                    std::fs::create_dir_all(target_path.parent().unwrap()).ok();
-                    let mut file_stream = File::create(&target_path).unwrap();
+        // the events are linked in the order of a hash map
+        let mut event_edges = self.edges.clone();
+        event_edges.sort_by(|(position, _), (other, _)| position.cmp(other));
 
-                    reference_node_ids.iter().for_each(|node_id| {
-                        let node = service_reference
-                            .service()
-                            .semantic()
-                            .nodes()
-                            .get_node(*node_id);
-                        let span = node.span();
-                        let text = service_reference
-                            .service()
-                            .semantic()
-                            .source_text()
-                            .get((span.start as usize)..(span.end as usize))
-                            .unwrap();
-
-                        file_stream
-                            .write_all((text.to_owned() + "\n\n").as_bytes())
-                            .ok();
-                    });
-                }
-            });
+        let edges = event_edges
+            .into_iter()
+            .map(|(_, edge)| edge)
+            .chain(flow_edges.into_iter().map(|(_, edge)| edge))
+            .collect();
+
+        let mut diagnostics: Vec<FileDiagnostic> = self
+            .diagnostics
+            .iter()
+            .map(|(source_path, diagnostic)| FileDiagnostic {
+                path: relative_path(&self.root_path, source_path).to_path_buf(),
+                diagnostic: diagnostic.clone(),
+            })
+            .collect();
+        let mut incomplete: Vec<PathBuf> = self
+            .incomplete_files()
+            .into_iter()
+            .map(|source_path| relative_path(&self.root_path, source_path).to_path_buf())
+            .collect();
+
+        for (path, diagnostic) in changed_files {
+            incomplete.push(path.clone());
+            diagnostics.push(FileDiagnostic { path, diagnostic });
+        }
+
+        // the diagnostics of a file stay in order
+        diagnostics.sort_by(|diagnostic, other| diagnostic.path.cmp(&other.path));
+        incomplete.sort();
+        incomplete.dedup();
+
+        ImpactReport {
+            units,
+            edges,
+            entry_points: self.impacted_entry_points(),
+            diagnostics,
+            incomplete,
+        }
+    }
+}
+
+/// `source_path` relative to the project, or without its root for a file out of it,
+/// e.g. a package of `node_modules` linked to another directory
+fn relative_path<'p>(root_path: &Path, source_path: &'p Path) -> &'p Path {
+    source_path.strip_prefix(root_path).unwrap_or_else(|_| {
+        let mut components = source_path.components();
+
+        while matches!(
+            components.clone().next(),
+            Some(Component::Prefix(_) | Component::RootDir)
+        ) {
+            components.next();
+        }
+
+        components.as_path()
+    })
+}
+
+/// Queues a query, `depth` hops away from the queried symbols, for every symbol impacted
+/// in `source_path` that hasn't been queried yet
+fn queue_impacted_symbols(
+    source_path: &Path,
+    service_reference: &ServiceReference,
+    depth: usize,
+    known_queries: &mut HashSet<SymbolKey>,
+    queries: &mut Vec<(SymbolKey, usize)>,
+) {
+    for symbol_id in service_reference.reference_symbol_ids() {
+        let query = SymbolKey::new(source_path.to_path_buf(), *symbol_id);
+
+        if known_queries.insert(query.clone()) {
+            queries.push((query, depth));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ReferenceKind;
+    use crate::testing::{analyze, bumblebee, project, unit_ids};
+
+    #[test]
+    fn build_with_the_config_of_the_project() {
+        let dir = project(&[(
+            ".bumblebee.toml",
+            "cache = false\nexclude = [\"legacy/**\"]\n",
+        )]);
+
+        let missing_root = Bumblebee::builder().build().err().unwrap();
+        let bumblebee = Bumblebee::builder().root(dir.path()).build().unwrap();
+
+        assert!(matches!(
+            missing_root.downcast_ref(),
+            Some(BumblebeeError::MissingRoot)
+        ));
+        assert_eq!(bumblebee.root_path(), realpath(dir.path()).unwrap());
+        assert_eq!(bumblebee.config().exclude, ["legacy/**"]);
+        assert!(!bumblebee.config().cache);
+    }
+
+    #[test]
+    fn analyze_repeatedly() {
+        let dir = project(&[
+            (
+                "cart.js",
+                "export const add = () => {};\nexport const remove = () => {};\n",
+            ),
+            (
+                "checkout.js",
+                "import { add } from \"./cart.js\";\nexport const buy = () => add();\n",
+            ),
+            (
+                "undo.js",
+                "import { remove } from \"./cart.js\";\nexport const undo = () => remove();\n",
+            ),
+        ]);
+        let mut bumblebee = bumblebee(dir.path(), Config::default());
+        let mut analyze = |symbol: &str| {
+            let query = SymbolQuery::new_with_symbol(symbol.into(), "cart.js".into());
+            bumblebee.analyze(&[Query::Symbol(query)]).unwrap()
+        };
+
+        let add = analyze("add");
+        let remove = analyze("remove");
+        let add_again = analyze("add");
+
+        assert_eq!(
+            unit_ids(&add),
+            ["cart.js#add", "checkout.js#<import@L1>", "checkout.js#buy"]
+        );
+        assert_eq!(
+            unit_ids(&remove),
+            ["cart.js#remove", "undo.js#<import@L1>", "undo.js#undo"]
+        );
+        assert_eq!(unit_ids(&add_again), unit_ids(&add));
+    }
+
+    #[test]
+    fn file_changed_since_indexed_is_incomplete() {
+        let dir = project(&[
+            ("cart.js", "export const add = () => {};\n"),
+            (
+                "checkout.js",
+                "import { add } from \"./cart.js\";\nexport const buy = () => add();\n",
+            ),
+        ]);
+        let mut bumblebee = bumblebee(dir.path(), Config::default());
+        let query = SymbolQuery::new_with_symbol("add".into(), "cart.js".into());
+        bumblebee.analyze(&[Query::Symbol(query.clone())]).unwrap();
+
+        // saved after it was indexed, and before it's updated
+        std::fs::write(dir.path().join("checkout.js"), "").unwrap();
+        let report = bumblebee.analyze(&[Query::Symbol(query)]).unwrap();
+        let texts: Vec<&str> = report.units.iter().map(|unit| unit.text.as_str()).collect();
+
+        assert_eq!(texts, ["export const add = () => {};", "", ""]);
+        assert_eq!(report.incomplete, [Path::new("checkout.js")]);
+        assert_eq!(
+            report.diagnostics,
+            [FileDiagnostic {
+                path: "checkout.js".into(),
+                diagnostic: Diagnostic::new(None, &BumblebeeError::ChangedSinceIndexed),
+            }]
+        );
+    }
+
+    #[test]
+    fn unresolved_imports_diagnosed_once() {
+        let dir = project(&[(
+            "app.js",
+            "import fs from \"fs\";\nimport { a } from \"./missing\";\nimport { b } from \"./missing\";\n",
+        )]);
+        let config = Config {
+            prefilter: false,
+            ..Default::default()
+        };
+        let bumblebee = bumblebee(dir.path(), config);
+        let diagnostics: Vec<(&Path, &str)> = bumblebee
+            .diagnostics()
+            .iter()
+            .map(|(path, diagnostic)| {
+                (
+                    relative_path(bumblebee.root_path(), path),
+                    diagnostic.message.as_str(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            diagnostics,
+            [(
+                Path::new("app.js"),
+                "Unresolved import `./missing`: Cannot find module './missing'"
+            )]
+        );
+    }
+
+    #[test]
+    fn problems_of_single_files_diagnosed() {
+        let dir = project(&[
+            ("cart.js", "export const add = () => {};\n"),
+            (
+                "checkout.js",
+                "import { add } from \"./cart.js\";\nconst plugin = require(name);\nexport const buy = () => add();\n",
+            ),
+        ]);
+        // not UTF-8, so it can't be read as source text
+        std::fs::write(dir.path().join("legacy.js"), b"const add = \"\xff\";\n").unwrap();
+        let config = Config {
+            prefilter: false,
+            ..Default::default()
+        };
+        let query = SymbolQuery::new_with_symbol("add".into(), "cart.js".into());
+        let report = bumblebee(dir.path(), config)
+            .analyze(&[Query::Symbol(query)])
+            .unwrap();
+        let diagnostics: Vec<(&Path, Option<usize>, &str)> = report
+            .diagnostics
+            .iter()
+            .map(|FileDiagnostic { path, diagnostic }| {
+                (path.as_path(), diagnostic.line, diagnostic.message.as_str())
+            })
+            .collect();
+
+        assert_eq!(
+            unit_ids(&report),
+            ["cart.js#add", "checkout.js#<import@L1>", "checkout.js#buy"]
+        );
+        assert_eq!(
+            diagnostics,
+            [
+                (
+                    Path::new("checkout.js"),
+                    Some(2),
+                    "`require` without a string specifier"
+                ),
+                (
+                    Path::new("legacy.js"),
+                    None,
+                    "Unable to read the file: stream did not contain valid UTF-8"
+                )
+            ]
+        );
+        assert_eq!(report.incomplete, [Path::new("legacy.js")]);
+    }
+
+    #[test]
+    fn syntax_errors_leave_files_incomplete() {
+        let dir = project(&[
+            ("cart.js", "export const add = () => {};\n"),
+            (
+                "checkout.js",
+                "import { add } from \"./cart.js\";\nreturn;\nexport const buy = () => add();\n",
+            ),
+        ]);
+        let query = SymbolQuery::new_with_symbol("add".into(), "cart.js".into());
+        let report = analyze(dir.path(), &[Query::Symbol(query)]);
+        let diagnostics: Vec<(&Path, Option<usize>)> = report
+            .diagnostics
+            .iter()
+            .map(|FileDiagnostic { path, diagnostic }| (path.as_path(), diagnostic.line))
+            .collect();
+
+        // a `return` out of a function is an error the parser recovers from
+        assert_eq!(
+            unit_ids(&report),
+            ["cart.js#add", "checkout.js#<import@L1>", "checkout.js#buy"]
+        );
+        assert_eq!(diagnostics, [(Path::new("checkout.js"), Some(2))]);
+        assert_eq!(report.incomplete, [Path::new("checkout.js")]);
+    }
+
+    #[test]
+    fn walk_included_files_and_scanned_packages() {
+        let dir = project(&[
+            (".ignore", "node_modules\n"),
+            ("src/cart.js", ""),
+            ("src/cart.test.js", ""),
+            ("scripts/build.js", ""),
+            ("node_modules/vendored/index.js", ""),
+            ("node_modules/lodash/index.js", ""),
+        ]);
+        let config = Config {
+            include: vec!["src/**".into()],
+            exclude: vec!["**/*.test.js".into()],
+            scan_packages: vec!["vendored".into()],
+            ..Default::default()
+        };
+        let bumblebee = bumblebee(dir.path(), config);
+        let mut source_paths: Vec<PathBuf> = bumblebee
+            .source_paths(None)
+            .unwrap()
+            .iter()
+            .map(|path| relative_path(bumblebee.root_path(), path).to_path_buf())
+            .collect();
+        source_paths.sort();
+
+        assert_eq!(
+            source_paths,
+            [
+                Path::new("node_modules/vendored/index.js"),
+                Path::new("src/cart.js")
+            ]
+        );
+    }
+
+    /// A project depending on lodash, installed with a `debounce` module of its own
+    fn lodash_project(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let lodash = [
+            (
+                "node_modules/lodash/package.json",
+                r#"{ "name": "lodash", "main": "lodash.js" }"#,
+            ),
+            (
+                "node_modules/lodash/lodash.js",
+                "exports.debounce = () => {};\n",
+            ),
+            (
+                "node_modules/lodash/debounce.js",
+                "module.exports = () => {};\n",
+            ),
+        ];
+
+        project(&[&lodash[..], files].concat())
+    }
+
+    fn debounce_query() -> Query {
+        Query::Package(PackageQuery::new("lodash".into(), "debounce".into()))
+    }
+
+    #[test]
+    fn package_imported_through_alias() {
+        let dir = lodash_project(&[
+            (
+                "search.js",
+                "import _ from \"lo\";\nexport const search = _.debounce(() => {});\nexport const other = _.throttle(() => {});\n",
+            ),
+            (
+                "save.js",
+                "import debounce from \"lo/debounce\";\nexport const save = debounce(() => {});\n",
+            ),
+        ]);
+        let config = Config {
+            aliases: [("lo".to_string(), vec!["./node_modules/lodash".to_string()])].into(),
+            ..Default::default()
+        };
+        let report = bumblebee(dir.path(), config)
+            .analyze(&[debounce_query()])
+            .unwrap();
+
+        assert_eq!(
+            unit_ids(&report),
+            [
+                "save.js#<import@L1>",
+                "save.js#save",
+                "search.js#<import@L1>",
+                "search.js#search"
+            ]
+        );
+    }
+
+    #[test]
+    fn package_required_in_place() {
+        let dir = lodash_project(&[
+            (
+                "search.js",
+                "export const search = require(\"lodash\").debounce(() => {});\nexport const other = require(\"lodash\").throttle(() => {});\n",
+            ),
+            (
+                "save.js",
+                "export const save = require(\"lodash/debounce\")(() => {});\n",
+            ),
+            (
+                "lodash-es.js",
+                "export const fork = require(\"lodash-es\").debounce(() => {});\n",
+            ),
+        ]);
+        let report = analyze(dir.path(), &[debounce_query()]);
+
+        assert_eq!(unit_ids(&report), ["save.js#save", "search.js#search"]);
+    }
+
+    #[test]
+    fn event_handlers_one_hop_after_the_emitter() {
+        let dir = project(&[
+            ("pay.js", "export const charge = () => {};\n"),
+            (
+                "orders.js",
+                "import { bus } from \"./events.js\";\nimport { charge } from \"./pay.js\";\nexport const pay = (order) => {\n  charge(order);\n  bus.emit(\"order:paid\", order);\n};\nexport const retry = (order) => pay(order);\nexport const retryLater = (order) => retry(order);\n",
+            ),
+            ("events.js", "export const bus = new EventTarget();\n"),
+            (
+                "receipts.js",
+                "import { bus } from \"./events.js\";\nconst sendReceipt = (order) => {};\nbus.on(\"order:paid\", sendReceipt);\nexport const resend = (order) => sendReceipt(order);\n",
+            ),
+        ]);
+        let config = Config {
+            max_depth: Some(2),
+            ..Default::default()
+        };
+        let query = SymbolQuery::new_with_symbol("charge".into(), "pay.js".into());
+        let report = bumblebee(dir.path(), config)
+            .analyze(&[Query::Symbol(query)])
+            .unwrap();
+
+        assert_eq!(
+            unit_ids(&report),
+            [
+                "orders.js#<import@L2>",
+                "orders.js#pay",
+                "orders.js#retry",
+                "orders.js#retryLater",
+                "pay.js#charge",
+                "receipts.js#sendReceipt",
+                "receipts.js#<statement@L3>",
+                "receipts.js#resend"
+            ]
+        );
+        assert_eq!(
+            report.edges,
+            [Edge {
+                kind: "event",
+                key: "order:paid".into(),
+                from: "orders.js:5".into(),
+                to: "receipts.js:3".into(),
+                heuristic: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn follow_only_chosen_reference_kinds() {
+        let dir = project(&[
+            ("config.js", "export const limit = 10;\n"),
+            (
+                "cart.js",
+                "import { limit } from \"./config.js\";\nexport const max = limit;\n",
+            ),
+            (
+                "checkout.js",
+                "import { max } from \"./cart.js\";\nexport const check = () => max;\n",
+            ),
+        ]);
+        let analyze_with = |config| {
+            let query = SymbolQuery::new_with_symbol("limit".into(), "config.js".into());
+            bumblebee(dir.path(), config)
+                .analyze(&[Query::Symbol(query)])
+                .unwrap()
+        };
+
+        let calls = analyze_with(Config {
+            follow: vec![ReferenceKind::Call],
+            ..Default::default()
+        });
+        let ignored_reads = analyze_with(Config {
+            ignore: vec![ReferenceKind::Read],
+            ..Default::default()
+        });
+        let reads = analyze_with(Config::default());
+
+        // the read is still reported, the impact just doesn't spread through it
+        assert_eq!(
+            unit_ids(&calls),
+            ["cart.js#<import@L1>", "cart.js#max", "config.js#limit"]
+        );
+        assert_eq!(unit_ids(&ignored_reads), unit_ids(&calls));
+        assert_eq!(
+            unit_ids(&reads),
+            [
+                "cart.js#<import@L1>",
+                "cart.js#max",
+                "checkout.js#<import@L1>",
+                "checkout.js#check",
+                "config.js#limit"
+            ]
+        );
+    }
+
+    #[test]
+    fn flow_edges() {
+        let dir = project(&[(
+            "notify.js",
+            "export const notify = () => {};\nfunction run(callback) {\n  callback();\n}\nrun(notify);\nconst handlers = {\n  paid: notify,\n  failed: notify,\n};\nhandlers.forEach((handler) => handler());\nconst make = () => notify;\nconst made = make();\n",
+        )]);
+        let query = SymbolQuery::new_with_symbol("notify".into(), "notify.js".into());
+        let report = analyze(dir.path(), &[Query::Symbol(query)]);
+        let edges: Vec<(&str, &str, &str, &str)> = report
+            .edges
+            .iter()
+            .map(|edge| {
+                (
+                    edge.kind,
+                    edge.key.as_str(),
+                    edge.from.as_str(),
+                    edge.to.as_str(),
+                )
+            })
+            .collect();
+
+        // `failed` is stored in `handlers` impacted by `paid` already
+        assert_eq!(
+            edges,
+            [
+                ("argument", "callback", "notify.js:5", "notify.js:2"),
+                ("stored", "handlers", "notify.js:7", "notify.js:6"),
+                ("stored", "handlers", "notify.js:8", "notify.js:6"),
+                ("iterated", "handler", "notify.js:10", "notify.js:10"),
+                ("returned", "made", "notify.js:11", "notify.js:12"),
+            ]
+        );
+    }
+
+    #[test]
+    fn event_edges_ordered_by_site() {
+        let dir = project(&[
+            ("bus.js", "export const bus = new EventTarget();\n"),
+            (
+                "save.js",
+                "import { bus } from \"./bus.js\";\nexport const save = () => {\n  bus.emit(\"saved\");\n};\nexport const saveAll = () => {\n  save();\n  bus.emit(\"saved\");\n};\n",
+            ),
+            (
+                "audit.js",
+                "import { bus } from \"./bus.js\";\nbus.on(\"saved\", () => {});\n",
+            ),
+            (
+                "cache.js",
+                "import { bus } from \"./bus.js\";\nbus.on(\"saved\", () => {});\n",
+            ),
+            (
+                "log.js",
+                "import { bus } from \"./bus.js\";\nbus.on(\"saved\", () => {});\n",
+            ),
+            (
+                "sync.js",
+                "import { bus } from \"./bus.js\";\nbus.on(\"saved\", () => {});\n",
+            ),
+        ]);
+        let query = SymbolQuery::new_with_symbol("save".into(), "save.js".into());
+        let report = analyze(dir.path(), &[Query::Symbol(query)]);
+        let edges: Vec<(&str, &str)> = report
+            .edges
+            .iter()
+            .map(|edge| (edge.from.as_str(), edge.to.as_str()))
+            .collect();
+
+        assert_eq!(
+            edges,
+            [
+                ("save.js:3", "audit.js:2"),
+                ("save.js:3", "cache.js:2"),
+                ("save.js:3", "log.js:2"),
+                ("save.js:3", "sync.js:2"),
+                ("save.js:7", "audit.js:2"),
+                ("save.js:7", "cache.js:2"),
+                ("save.js:7", "log.js:2"),
+                ("save.js:7", "sync.js:2")
+            ]
+        );
+    }
+
+    #[test]
+    fn update_changed_files_only() {
+        let dir = project(&[
+            ("factory.js", "export const call = () => {};\n"),
+            ("api.js", "export * from \"./factory.js\";\n"),
+            (
+                "app.js",
+                "import { call } from \"./api.js\";\nexport const run = () => call();\n",
+            ),
+        ]);
+        let config = Config {
+            exclude: vec!["**/*.test.js".into()],
+            ..Default::default()
+        };
+        let mut bumblebee = bumblebee(dir.path(), config);
+        let root_path = bumblebee.root_path().to_path_buf();
+        let call_query =
+            |path: &str| Query::Symbol(SymbolQuery::new_with_symbol("call".into(), path.into()));
+        let write = |path: &str, contents: &str| {
+            std::fs::write(root_path.join(path), contents).unwrap();
+            root_path.join(path)
+        };
+
+        let report = bumblebee.analyze(&[call_query("factory.js")]).unwrap();
+
+        // `call` moves to a new module the barrel re-exports instead, without `app.js`
+        // being indexed again
+        let moved = [
+            write("util.js", "export const call = () => {};\n"),
+            write("api.js", "export * from \"./util.js\";\n"),
+            write("factory.js", "export const make = () => {};\n"),
+        ];
+        assert!(bumblebee.update_files(&moved).unwrap());
+        let moved_report = bumblebee.analyze(&[call_query("util.js")]).unwrap();
+
+        let test = write(
+            "app.test.js",
+            "import { call } from \"./util.js\";\ncall();\n",
+        );
+        assert!(!bumblebee.update_files(&[test]).unwrap());
+
+        std::fs::remove_file(root_path.join("app.js")).unwrap();
+        assert!(bumblebee.update_files(&[root_path.join("app.js")]).unwrap());
+        let deleted_report = bumblebee.analyze(&[call_query("util.js")]).unwrap();
+
+        assert_eq!(
+            unit_ids(&report),
+            ["app.js#<import@L1>", "app.js#run", "factory.js#call"]
+        );
+        assert_eq!(
+            unit_ids(&moved_report),
+            ["app.js#<import@L1>", "app.js#run", "util.js#call"]
+        );
+        assert_eq!(unit_ids(&deleted_report), ["util.js#call"]);
     }
 }
@@ -0,0 +1,57 @@
+use crate::error::BumblebeeError;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// A problem with a file that doesn't stop the analysis, e.g. an import that doesn't
+/// resolve or a file that can't be read
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// 1-based line the problem is at, `None` when it's about the whole file
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(line: Option<usize>, error: &BumblebeeError) -> Self {
+        Self {
+            line,
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Diagnostics of the files of the project, replaced whenever a file is indexed again
+#[derive(Default)]
+pub struct Diagnostics {
+    files: BTreeMap<PathBuf, Vec<Diagnostic>>,
+}
+
+impl Diagnostics {
+    /// Replaces the diagnostics of the file at `path`
+    pub fn set(&mut self, path: &Path, diagnostics: Vec<Diagnostic>) {
+        if diagnostics.is_empty() {
+            self.files.remove(path);
+        } else {
+            self.files.insert(path.to_path_buf(), diagnostics);
+        }
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        self.files.remove(path);
+    }
+
+    /// Paths of the files with diagnostics
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files.keys()
+    }
+
+    /// Every diagnostic with the path of its file, ordered by path
+    pub fn iter(&self) -> impl Iterator<Item = (&PathBuf, &Diagnostic)> {
+        self.files.iter().flat_map(|(path, diagnostics)| {
+            diagnostics.iter().map(move |diagnostic| (path, diagnostic))
+        })
+    }
+}
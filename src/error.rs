@@ -0,0 +1,32 @@
+use std::{io, path::PathBuf};
+use thiserror::Error;
+
+/// What can go wrong while analyzing a project
+///
+/// Errors about the whole run, e.g. an invalid project path, stop it. The ones about a
+/// single file become diagnostics of that file, and the analysis goes on without it.
+#[derive(Error, Debug)]
+pub enum BumblebeeError {
+    #[error("No project root given")]
+    MissingRoot,
+    #[error("Invalid project path {}", path.display())]
+    ProjectPath { path: PathBuf, source: io::Error },
+    #[error("Invalid query path {}", path.display())]
+    QueryPath { path: PathBuf, source: io::Error },
+    #[error("Package {0} is not installed")]
+    PackageNotInstalled(String),
+    #[error("Unable to read the file: {0}")]
+    UnreadableFile(io::Error),
+    #[error("The file changed since it was indexed, its snippets aren't reported")]
+    ChangedSinceIndexed,
+    #[error("Unsupported file type")]
+    UnsupportedFileType,
+    #[error("Unresolved import `{specifier}`: {reason}")]
+    UnresolvedImport { specifier: String, reason: String },
+    #[error("`require` without a string specifier")]
+    MalformedRequire,
+    #[error("Syntax error: {0}")]
+    Syntax(String),
+    #[error("Unable to recover from the syntax errors, none of the file is analyzed")]
+    Unparsable,
+}
@@ -0,0 +1,179 @@
+use crate::linker::{ExportTarget, ModuleInterface};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+/// File-level reverse import graph: which files import or re-export each file
+#[derive(Default)]
+pub struct ImportGraph {
+    importers: HashMap<PathBuf, HashSet<PathBuf>>,
+    re_exporters: HashMap<PathBuf, HashSet<PathBuf>>,
+    /// Files each file imports or re-exports, for its edges to be removed when it changes
+    sources: HashMap<PathBuf, Vec<PathBuf>>,
+}
+
+impl ImportGraph {
+    pub fn build(interfaces: &HashMap<PathBuf, ModuleInterface>) -> Self {
+        let mut graph = Self::default();
+        graph.update(interfaces, interfaces.keys());
+
+        graph
+    }
+
+    /// Replaces the edges of the changed `files`, dropping the ones missing from
+    /// `interfaces` since they were deleted
+    pub fn update<'f>(
+        &mut self,
+        interfaces: &HashMap<PathBuf, ModuleInterface>,
+        files: impl IntoIterator<Item = &'f PathBuf>,
+    ) {
+        for file in files {
+            self.remove(file);
+
+            if let Some(interface) = interfaces.get(file) {
+                self.add(file, interface);
+            }
+        }
+    }
+
+    fn add(&mut self, file: &Path, interface: &ModuleInterface) {
+        let import_sources: Vec<&PathBuf> = interface
+            .imports
+            .iter()
+            .filter_map(|import| import.source.as_ref())
+            .collect();
+        let re_export_sources: Vec<&PathBuf> = interface
+            .exports
+            .values()
+            .filter_map(|target| match target {
+                ExportTarget::ReExport { source, .. } => source.as_ref(),
+                ExportTarget::Local(_) => None,
+            })
+            .chain(&interface.star_exports)
+            .collect();
+
+        for source in &import_sources {
+            self.importers
+                .entry(source.to_path_buf())
+                .or_default()
+                .insert(file.to_owned());
+        }
+
+        for source in &re_export_sources {
+            self.re_exporters
+                .entry(source.to_path_buf())
+                .or_default()
+                .insert(file.to_owned());
+        }
+
+        self.sources.insert(
+            file.to_owned(),
+            import_sources
+                .into_iter()
+                .chain(re_export_sources)
+                .cloned()
+                .collect(),
+        );
+    }
+
+    fn remove(&mut self, file: &Path) {
+        for source in self.sources.remove(file).into_iter().flatten() {
+            for edges in [&mut self.importers, &mut self.re_exporters] {
+                if let Some(files) = edges.get_mut(&source) {
+                    files.remove(file);
+                }
+            }
+        }
+    }
+
+    /// The files a symbol of `files` can be referenced from: the files themselves, the
+    /// files importing them and, through their re-exports, the files importing those
+    pub fn dependents<'f>(&self, files: impl IntoIterator<Item = &'f Path>) -> HashSet<PathBuf> {
+        let mut modules: Vec<PathBuf> = files.into_iter().map(Path::to_path_buf).collect();
+        let mut dependents: HashSet<PathBuf> = modules.iter().cloned().collect();
+        // a barrel can import from a module as well as re-export it, so the modules whose
+        // importers were walked are told apart from the dependents
+        let mut visited = dependents.clone();
+
+        while let Some(module) = modules.pop() {
+            if let Some(importers) = self.importers.get(&module) {
+                dependents.extend(importers.iter().cloned());
+            }
+
+            for re_exporter in self.re_exporters.get(&module).into_iter().flatten() {
+                dependents.insert(re_exporter.to_owned());
+
+                if visited.insert(re_exporter.to_owned()) {
+                    modules.push(re_exporter.to_owned());
+                }
+            }
+        }
+
+        dependents
+    }
+
+    /// The files re-exporting `file`, directly or through other re-exports
+    pub fn re_exporters(&self, file: &Path) -> HashSet<PathBuf> {
+        let mut re_exporters = HashSet::new();
+        let mut modules = vec![file.to_path_buf()];
+
+        while let Some(module) = modules.pop() {
+            for re_exporter in self.re_exporters.get(&module).into_iter().flatten() {
+                if re_exporters.insert(re_exporter.to_owned()) {
+                    modules.push(re_exporter.to_owned());
+                }
+            }
+        }
+
+        re_exporters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linker::{ImportBinding, ImportName};
+    use oxc_semantic::SymbolId;
+
+    fn import(source: &str, name: &str) -> ImportBinding {
+        ImportBinding {
+            symbol_id: SymbolId::new(0),
+            specifier: format!("./{source}"),
+            is_require: false,
+            source: Some(source.into()),
+            name: ImportName::Named(name.into()),
+        }
+    }
+
+    #[test]
+    fn dependents_through_importing_barrel() {
+        // barrel.js: import { helper } from "./a.js"; export { x } from "./a.js";
+        let barrel = ModuleInterface {
+            imports: vec![import("a.js", "helper")],
+            exports: HashMap::from([(
+                "x".to_string(),
+                ExportTarget::ReExport {
+                    source: Some("a.js".into()),
+                    name: ImportName::Named("x".into()),
+                },
+            )]),
+            star_exports: Vec::new(),
+        };
+        let consumer = ModuleInterface {
+            imports: vec![import("barrel.js", "x")],
+            ..Default::default()
+        };
+        let interfaces = HashMap::from([
+            (PathBuf::from("a.js"), ModuleInterface::default()),
+            (PathBuf::from("barrel.js"), barrel),
+            (PathBuf::from("consumer.js"), consumer),
+        ]);
+        let graph = ImportGraph::build(&interfaces);
+
+        assert_eq!(
+            graph.dependents([Path::new("a.js")]),
+            HashSet::from(["a.js".into(), "barrel.js".into(), "consumer.js".into()])
+        );
+    }
+}
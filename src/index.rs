@@ -0,0 +1,1731 @@
+use crate::config::{EventPattern, EventRole, Granularity, ReferenceKind};
+use crate::diagnostics::Diagnostic;
+use crate::error::BumblebeeError;
+use crate::linker::{ExportTarget, ImportBinding, ImportName, ModuleInterface};
+use oxc_ast::{
+    ast::{
+        Argument, ArrayPattern, BindingPattern, BindingPatternKind, Declaration,
+        ExportDefaultDeclaration, ExportDefaultDeclarationKind, Expression, ForStatementLeft,
+        FormalParameters, ImportDeclarationSpecifier, ModuleDeclaration, ModuleExportName,
+        ObjectPattern, PropertyKey, UnaryOperator, VariableDeclarator,
+    },
+    AstKind,
+};
+use oxc_index::Idx;
+use oxc_semantic::{AstNode, AstNodes, NodeId, Semantic, SymbolId};
+use oxc_span::{Atom, GetSpan, Span};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+/// Where a node sits in its file: the top-level statement and the unit enclosing it, and
+/// the functions and variables declared on the way down to it, which the node impacts
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Site {
+    /// Index of the enclosing top-level statement in [`FileIndex::statements`], `None`
+    /// for the program itself
+    pub statement: Option<usize>,
+    /// Index of the innermost enclosing unit in [`FileIndex::units`]
+    pub unit: Option<usize>,
+    #[serde(with = "serde_ids::symbol_ids")]
+    pub symbols: Vec<SymbolId>,
+}
+
+/// A piece of code the impacted code is reported in
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Unit {
+    #[serde(with = "serde_ids::span")]
+    pub span: Span,
+    /// Names of the code enclosing the unit down to the unit itself, e.g.
+    /// `checkout.place.<callback@L12>`, empty for the whole file. A name already taken
+    /// by a previous unit gets the line and column the unit starts at, e.g. `main@L6:3`.
+    pub name: String,
+}
+
+/// A property of `const { VAR: value } = object`, with the bindings of its value
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DestructuredProperty {
+    pub key: String,
+    #[serde(with = "serde_ids::symbol_ids")]
+    pub symbol_ids: Vec<SymbolId>,
+}
+
+/// How a value reaches a binding that doesn't reference it by name
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flow {
+    /// Passed to a function of the file, e.g. `run(handler)`, reaching its parameter
+    Argument,
+    /// Stored in an array or an object, e.g. `handlers.push(handler)`, reaching the
+    /// variable holding it
+    Stored,
+    /// Returned by a function of the file, reaching the variables its calls initialize
+    Returned,
+    /// Iterated over, e.g. `handlers.forEach((fn) => fn())`, reaching the parameter of
+    /// the callback
+    Iterated,
+}
+
+impl Flow {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Argument => "argument",
+            Self::Stored => "stored",
+            Self::Returned => "returned",
+            Self::Iterated => "iterated",
+        }
+    }
+}
+
+/// A binding of the file a referenced value flows into
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Receiver {
+    #[serde(with = "serde_ids::symbol_id")]
+    pub symbol_id: SymbolId,
+    pub flow: Flow,
+    /// Line the binding is declared on
+    pub line: usize,
+}
+
+/// Array methods calling their callback with the elements, e.g. `forEach`
+const ITERATION_METHODS: [&str; 10] = [
+    "forEach",
+    "map",
+    "flatMap",
+    "filter",
+    "find",
+    "findLast",
+    "some",
+    "every",
+    "reduce",
+    "reduceRight",
+];
+
+/// Methods storing their arguments in the collection they're called on
+const STORE_METHODS: [&str; 4] = ["push", "unshift", "add", "set"];
+
+/// A reference to a symbol or a global, e.g. `window` of `window.localStorage.setItem()`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReferenceSite {
+    pub site: Site,
+    pub line: usize,
+    /// How the reference uses what it references, through its member accesses, e.g. a
+    /// call for `window.localStorage.setItem()`
+    pub kind: ReferenceKind,
+    /// The static member accesses the reference is the object of, e.g.
+    /// `["localStorage", "setItem"]`
+    pub members: Vec<String>,
+    /// The properties destructured from the last member access, e.g. `VAR` of
+    /// `const { VAR } = process.env`
+    pub destructured: Vec<DestructuredProperty>,
+    /// The bindings the value of the last member access flows into
+    pub receivers: Vec<Receiver>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SymbolEntry {
+    pub name: String,
+    /// Whether the symbol is declared in the module scope
+    pub root: bool,
+    pub declaration: Site,
+    pub references: Vec<ReferenceSite>,
+}
+
+/// A string constant of the file, e.g. a feature flag passed to `isEnabled`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StringSite {
+    pub value: String,
+    pub site: Site,
+}
+
+/// A `require` call whose module is used in place rather than bound to a variable, e.g.
+/// `require("lodash").debounce(search)`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InlineRequire {
+    /// The specifier as written, e.g. `lodash`
+    pub specifier: String,
+    /// The required file, `None` when the specifier doesn't resolve to a file
+    pub source: Option<PathBuf>,
+    /// The call itself, with the members accessed on what it returns
+    pub reference: ReferenceSite,
+}
+
+/// What a listener subscribes to an event
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum Handler {
+    /// A function declared elsewhere, e.g. `sendReceipt` of `bus.on(key, sendReceipt)`
+    Symbol(#[serde(with = "serde_ids::symbol_id")] SymbolId),
+    /// A function written in place, e.g. `bus.on(key, (order) => {})`
+    Inline(Site),
+}
+
+/// A call sending or listening to an event, linked to the other side by its key
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EventSite {
+    pub key: String,
+    pub role: EventRole,
+    /// Span of the call, e.g. `bus.emit("order:paid", order)`
+    #[serde(with = "serde_ids::span")]
+    pub span: Span,
+    pub line: usize,
+    pub handler: Option<Handler>,
+}
+
+/// Everything the impact analysis needs to know about a file, extracted from its AST
+/// once so the AST can be dropped, and cached between runs
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct FileIndex {
+    /// Spans of the top-level statements, the code the impact spreads through
+    #[serde(with = "serde_ids::spans")]
+    pub statements: Vec<Span>,
+    /// Units the impacted code is reported in, of the configured granularity
+    pub units: Vec<Unit>,
+    /// Offsets the lines of the file start at
+    pub line_starts: Vec<u32>,
+    /// Declared symbols, by `SymbolId`
+    pub symbols: Vec<SymbolEntry>,
+    /// References to globals without declaration, by name
+    pub unresolved_references: HashMap<String, Vec<ReferenceSite>>,
+    /// `import.meta` meta properties
+    pub import_meta: Vec<ReferenceSite>,
+    pub strings: Vec<StringSite>,
+    pub event_sites: Vec<EventSite>,
+    pub interface: ModuleInterface,
+    pub inline_requires: Vec<InlineRequire>,
+    /// Every specifier of the file with the file it resolved to, telling whether the
+    /// index is still up to date once files were added, deleted or the aliases changed
+    pub resolutions: Vec<(String, Option<PathBuf>)>,
+    /// Problems found while parsing the file and extracting the index, e.g. syntax errors
+    pub diagnostics: Vec<Diagnostic>,
+    /// Whether syntax errors may have left code of the file out of the index
+    pub incomplete: bool,
+}
+
+impl FileIndex {
+    /// Extracts the index of a file, resolving its import specifiers with `resolve`
+    pub fn build(
+        semantic: &Semantic,
+        events: &[EventPattern],
+        granularity: Granularity,
+        resolve: impl Fn(&str) -> Option<PathBuf>,
+    ) -> Self {
+        let nodes = semantic.nodes();
+        let scoping = semantic.scoping();
+        let extractor = Extractor::new(semantic, granularity);
+
+        let symbols = scoping
+            .symbol_ids()
+            .map(|symbol_id| {
+                let name = scoping.symbol_name(symbol_id);
+
+                SymbolEntry {
+                    name: name.into(),
+                    root: scoping.get_root_binding(name) == Some(symbol_id),
+                    declaration: extractor.site(scoping.symbol_declaration(symbol_id)),
+                    references: semantic
+                        .symbol_references(symbol_id)
+                        .map(|reference| extractor.reference_site(reference.node_id()))
+                        .collect(),
+                }
+            })
+            .collect();
+
+        let unresolved_references = scoping
+            .root_unresolved_references()
+            .iter()
+            .map(|(name, reference_ids)| {
+                let references = reference_ids
+                    .iter()
+                    .map(|reference_id| {
+                        let node_id = scoping.get_reference(*reference_id).node_id();
+                        extractor.reference_site(node_id)
+                    })
+                    .collect();
+
+                (name.to_string(), references)
+            })
+            .collect();
+
+        let import_meta = nodes
+            .iter()
+            .filter(|node| {
+                node.kind().as_meta_property().is_some_and(|meta_property| {
+                    meta_property.meta.name == "import" && meta_property.property.name == "meta"
+                })
+            })
+            .map(|node| extractor.reference_site(node.id()))
+            .collect();
+
+        let resolutions = RefCell::new(Vec::new());
+        let resolve = |specifier: &str| {
+            let source = resolve(specifier);
+            resolutions
+                .borrow_mut()
+                .push((specifier.to_string(), source.clone()));
+
+            source
+        };
+
+        let mut diagnostics = Vec::new();
+        let interface = extractor.module_interface(resolve, &mut diagnostics);
+        let inline_requires = extractor.inline_requires(resolve);
+        let mut resolutions = resolutions.into_inner();
+        resolutions.sort_unstable();
+        resolutions.dedup();
+
+        Self {
+            statements: extractor.statements.iter().map(|(_, span)| *span).collect(),
+            units: extractor.units(),
+            line_starts: extractor.line_starts.clone(),
+            symbols,
+            unresolved_references,
+            import_meta,
+            strings: extractor.strings(),
+            event_sites: extractor.event_sites(events),
+            interface,
+            inline_requires,
+            resolutions,
+            diagnostics,
+            incomplete: false,
+        }
+    }
+
+    /// The 1-based line of the byte `offset`
+    pub fn line(&self, offset: u32) -> usize {
+        self.line_starts.partition_point(|start| *start <= offset)
+    }
+
+    /// The module-level symbol named `symbol_name`, or else the first symbol of a nested
+    /// scope with that name
+    pub fn symbol_id(&self, symbol_name: &str) -> Option<SymbolId> {
+        let position = self
+            .symbols
+            .iter()
+            .position(|symbol| symbol.root && symbol.name == symbol_name)
+            .or_else(|| {
+                self.symbols
+                    .iter()
+                    .position(|symbol| symbol.name == symbol_name)
+            })?;
+
+        Some(SymbolId::new(position as u32))
+    }
+
+    pub fn symbol(&self, symbol_id: SymbolId) -> &SymbolEntry {
+        &self.symbols[symbol_id.index()]
+    }
+}
+
+/// Walks the semantic of a file into the tables of its index
+struct Extractor<'s, 'a> {
+    semantic: &'s Semantic<'a>,
+    /// Top-level statements and their spans, in source order
+    statements: Vec<(NodeId, Span)>,
+    /// Units and their spans, ordered by `NodeId`, so the enclosing ones come first
+    units: Vec<(NodeId, Span)>,
+    line_starts: Vec<u32>,
+}
+
+impl<'s, 'a> Extractor<'s, 'a> {
+    fn new(semantic: &'s Semantic<'a>, granularity: Granularity) -> Self {
+        let nodes = semantic.nodes();
+        let statements = nodes
+            .iter()
+            .filter(|node| matches!(nodes.parent_kind(node.id()), Some(AstKind::Program(_))))
+            .map(|node| (node.id(), node.span()))
+            .collect();
+
+        let mut unit_ids: Vec<NodeId> = nodes
+            .iter()
+            .filter(|node| is_unit(node.kind(), nodes.parent_kind(node.id()), granularity))
+            .map(|node| unit_node(nodes, node.id()))
+            .collect();
+        unit_ids.sort_unstable();
+        unit_ids.dedup();
+
+        let units = unit_ids
+            .into_iter()
+            .map(|unit_id| (unit_id, nodes.get_node(unit_id).span()))
+            .collect();
+
+        let line_starts = std::iter::once(0)
+            .chain(
+                semantic
+                    .source_text()
+                    .match_indices('\n')
+                    .map(|(i, _)| i as u32 + 1),
+            )
+            .collect();
+
+        Self {
+            semantic,
+            statements,
+            units,
+            line_starts,
+        }
+    }
+
+    /// The site of a node: the outermost statement and the innermost unit enclosing it,
+    /// and the symbols declared by the functions and variable declarators on the way
+    fn site(&self, node_id: NodeId) -> Site {
+        let mut statement = None;
+        let mut unit = None;
+        let mut symbols = Vec::new();
+
+        for ancestor in self.semantic.nodes().ancestors(node_id) {
+            if unit.is_none() {
+                unit = self
+                    .units
+                    .binary_search_by_key(&ancestor.id(), |(id, _)| *id)
+                    .ok();
+            }
+
+            match ancestor.kind() {
+                AstKind::Program(_) => {}
+                AstKind::Function(func) => {
+                    if let Some(id) = &func.id {
+                        symbols.push(id.symbol_id());
+                    }
+                    statement = Some(ancestor.id());
+                }
+                AstKind::VariableDeclarator(vd) => {
+                    get_symbol_ids_from_variable_declarator(vd, &mut symbols);
+                    statement = Some(ancestor.id());
+                }
+                _ => {
+                    statement = Some(ancestor.id());
+                }
+            }
+        }
+
+        Site {
+            statement: statement.and_then(|statement| {
+                self.statements
+                    .binary_search_by_key(&statement, |(id, _)| *id)
+                    .ok()
+            }),
+            unit,
+            symbols,
+        }
+    }
+
+    /// The units of the file, with names unique within it
+    fn units(&self) -> Vec<Unit> {
+        let mut names = HashSet::new();
+
+        self.units
+            .iter()
+            .map(|(unit_id, span)| {
+                let name = self.unit_name(*unit_id);
+                let line = self.line(span.start);
+                let column = span.start - self.line_starts[line - 1] + 1;
+
+                // e.g. `const { main } = { main() {} }` and the `main` it takes, or the
+                // callbacks chained on one line
+                let name = [name.clone(), format!("{name}@L{line}:{column}")]
+                    .into_iter()
+                    .chain((2..).map(|count| format!("{name}@L{line}:{column}~{count}")))
+                    .find(|candidate| names.insert(candidate.clone()))
+                    .unwrap_or_default();
+
+                Unit { span: *span, name }
+            })
+            .collect()
+    }
+
+    /// The name of a unit, from the functions, classes, object keys and variables
+    /// enclosing it down to its own
+    fn unit_name(&self, unit_id: NodeId) -> String {
+        let nodes = self.semantic.nodes();
+        let unit = nodes.get_node(unit_id);
+
+        if matches!(unit.kind(), AstKind::Program(_)) {
+            return String::new();
+        }
+
+        let mut names: Vec<String> = nodes
+            .ancestors(unit_id)
+            .skip(1)
+            .filter_map(|ancestor| self.scope_name(ancestor))
+            .collect();
+        names.reverse();
+
+        names.push(
+            self.scope_name(unit)
+                .or_else(|| self.declared_name(unit.kind()))
+                .unwrap_or_else(|| self.synthetic_name(unit)),
+        );
+
+        names.join(".")
+    }
+
+    /// The name a node gives to the code nested in it: the name of a function, class or
+    /// variable, or the key of a property. Functions and classes are named by their
+    /// parent when they are its value, e.g. `const main = () => {}`. A destructuring
+    /// doesn't name its value, e.g. `const { main } = { main() {} }` only holds `main`.
+    fn scope_name(&self, node: &AstNode) -> Option<String> {
+        let is_named_by_parent = matches!(
+            self.semantic.nodes().parent_kind(node.id()),
+            Some(
+                AstKind::VariableDeclarator(_)
+                    | AstKind::ObjectProperty(_)
+                    | AstKind::MethodDefinition(_)
+                    | AstKind::PropertyDefinition(_)
+                    | AstKind::AssignmentExpression(_)
+                    | AstKind::ExportDefaultDeclaration(_)
+            )
+        );
+
+        match node.kind() {
+            AstKind::Function(_) | AstKind::ArrowFunctionExpression(_) | AstKind::Class(_)
+                if is_named_by_parent =>
+            {
+                None
+            }
+            AstKind::Function(func) => Some(
+                func.id
+                    .as_ref()
+                    .map_or_else(|| self.synthetic_name(node), |id| id.name.to_string()),
+            ),
+            AstKind::Class(class) => Some(
+                class
+                    .id
+                    .as_ref()
+                    .map_or_else(|| self.synthetic_name(node), |id| id.name.to_string()),
+            ),
+            AstKind::ArrowFunctionExpression(_) => Some(self.synthetic_name(node)),
+            AstKind::VariableDeclarator(vd) => match &vd.id.kind {
+                BindingPatternKind::BindingIdentifier(id) => Some(id.name.to_string()),
+                _ => None,
+            },
+            AstKind::ObjectProperty(property) => Some(self.key_name(&property.key)),
+            AstKind::MethodDefinition(method) => Some(self.key_name(&method.key)),
+            AstKind::PropertyDefinition(property) => Some(self.key_name(&property.key)),
+            // module.exports.handler = () => {}
+            AstKind::AssignmentExpression(assignment) => {
+                assignment.left.get_identifier_name().map(String::from)
+            }
+            AstKind::ExportDefaultDeclaration(export_declaration) => {
+                Some(default_export_name(export_declaration))
+            }
+            _ => None,
+        }
+    }
+
+    /// The names a statement declares, e.g. `main` of `export const main = () => {}`
+    fn declared_name(&self, kind: AstKind) -> Option<String> {
+        let declaration = match kind {
+            AstKind::ModuleDeclaration(ModuleDeclaration::ExportNamedDeclaration(
+                export_declaration,
+            )) => export_declaration.declaration.as_ref()?,
+            AstKind::ModuleDeclaration(ModuleDeclaration::ExportDefaultDeclaration(
+                export_declaration,
+            )) => return Some(default_export_name(export_declaration)),
+            AstKind::ExportNamedDeclaration(export_declaration) => {
+                export_declaration.declaration.as_ref()?
+            }
+            AstKind::VariableDeclaration(variable_declaration) => {
+                return self.binding_names(&variable_declaration.declarations);
+            }
+            _ => return None,
+        };
+
+        match declaration {
+            Declaration::VariableDeclaration(variable_declaration) => {
+                self.binding_names(&variable_declaration.declarations)
+            }
+            _ => declaration.id().map(|id| id.name.to_string()),
+        }
+    }
+
+    /// Names of the variables declared by `declarators`, e.g. `a,b` of `const [a, b] = c`
+    fn binding_names(&self, declarators: &[VariableDeclarator]) -> Option<String> {
+        let mut symbol_ids = Vec::new();
+
+        for vd in declarators {
+            get_symbol_ids_from_variable_declarator(vd, &mut symbol_ids);
+        }
+
+        let scoping = self.semantic.scoping();
+        let names: Vec<&str> = symbol_ids
+            .into_iter()
+            .map(|symbol_id| scoping.symbol_name(symbol_id))
+            .collect();
+
+        (!names.is_empty()).then(|| names.join(","))
+    }
+
+    /// The static name of a property key, or `<computed@L3>` for `[key]`
+    fn key_name(&self, key: &PropertyKey) -> String {
+        key.static_name().map_or_else(
+            || format!("<computed@L{}>", self.line(key.span().start)),
+            String::from,
+        )
+    }
+
+    /// A name for the code without one, by what it is and the line it starts on:
+    /// `<iife@L31>` for a function called right away, `<callback@L12>` for one passed as
+    /// an argument, `<anonymous@L3>` for any other function, and `<import@L1>`,
+    /// `<export@L2>` or `<statement@L7>` for statements
+    fn synthetic_name(&self, node: &AstNode) -> String {
+        let parent_kind = self
+            .semantic
+            .nodes()
+            .ancestors(node.id())
+            .skip(1)
+            .map(|ancestor| ancestor.kind())
+            .find(|kind| !matches!(kind, AstKind::ParenthesizedExpression(_)));
+        let is_function = node.kind().is_function_like();
+
+        let kind = match (node.kind(), parent_kind) {
+            (AstKind::Class(_), _) => "class",
+            (_, Some(AstKind::CallExpression(_))) if is_function => "iife",
+            (AstKind::CallExpression(_), _) => "iife",
+            (AstKind::ExpressionStatement(statement), _) if is_iife(&statement.expression) => {
+                "iife"
+            }
+            (_, Some(AstKind::Argument(_))) if is_function => "callback",
+            _ if is_function => "anonymous",
+            (AstKind::ModuleDeclaration(module_declaration), _)
+                if module_declaration.is_import() =>
+            {
+                "import"
+            }
+            (AstKind::ImportDeclaration(_), _) => "import",
+            (AstKind::ModuleDeclaration(_), _) => "export",
+            _ => "statement",
+        };
+
+        format!("<{kind}@L{}>", self.line(node.kind().span().start))
+    }
+
+    fn reference_site(&self, node_id: NodeId) -> ReferenceSite {
+        let nodes = self.semantic.nodes();
+        let mut members = Vec::new();
+        let mut object_id = node_id;
+
+        while let Some(parent) = nodes.parent_node(object_id) {
+            let Some(member_expression) = parent.kind().as_member_expression() else {
+                break;
+            };
+
+            if member_expression.object().span() != nodes.get_node(object_id).span() {
+                break;
+            }
+
+            let Some(member) = member_expression.static_property_name() else {
+                break;
+            };
+
+            members.push(member.to_string());
+            object_id = parent.id();
+        }
+
+        let mut destructured = Vec::new();
+
+        // const { VAR } = process.env
+        if let Some(AstKind::VariableDeclarator(vd)) = nodes.parent_kind(object_id) {
+            if let BindingPatternKind::ObjectPattern(object_pattern) = &vd.id.kind {
+                for prop in object_pattern.properties.iter() {
+                    let Some(key) = prop.key.static_name() else {
+                        continue;
+                    };
+                    let mut symbol_ids = Vec::new();
+                    get_symbol_ids_from_binding_pattern(&prop.value, &mut symbol_ids);
+
+                    destructured.push(DestructuredProperty {
+                        key: key.to_string(),
+                        symbol_ids,
+                    });
+                }
+            }
+        }
+
+        ReferenceSite {
+            site: self.site(node_id),
+            line: self.line(nodes.kind(node_id).span().start),
+            kind: self.reference_kind(node_id, object_id),
+            members,
+            destructured,
+            receivers: self.receivers(object_id),
+        }
+    }
+
+    /// The bindings of this file the value at `value_id` flows into without referencing
+    /// it by name: the parameter it's passed to, the collection it's stored in, the
+    /// variables holding what the function returning it returns, and the parameter of
+    /// the callback iterating over it
+    fn receivers(&self, value_id: NodeId) -> Vec<Receiver> {
+        let nodes = self.semantic.nodes();
+        let mut receivers = Vec::new();
+        let Some(parent) = self.value_parent(value_id) else {
+            return receivers;
+        };
+
+        match parent.kind() {
+            AstKind::Argument(_) => self.argument_receivers(parent, &mut receivers),
+            AstKind::ArrayExpressionElement(_) | AstKind::ObjectProperty(_) => {
+                // const handlers = { paid: [sendReceipt] }
+                let container = nodes.ancestors(parent.id()).find(|ancestor| {
+                    !matches!(
+                        ancestor.kind(),
+                        AstKind::ArrayExpressionElement(_)
+                            | AstKind::ArrayExpression(_)
+                            | AstKind::ObjectProperty(_)
+                            | AstKind::ObjectExpression(_)
+                            | AstKind::ParenthesizedExpression(_)
+                    )
+                });
+
+                if let Some(AstKind::VariableDeclarator(vd)) = container.map(AstNode::kind) {
+                    receivers.extend(self.binding_receiver(&vd.id, Flow::Stored));
+                }
+            }
+            AstKind::ReturnStatement(_) => self.returned_receivers(parent, &mut receivers),
+            // () => handler
+            AstKind::ExpressionStatement(_)
+                if matches!(
+                    nodes.ancestors(parent.id()).nth(2).map(AstNode::kind),
+                    Some(AstKind::ArrowFunctionExpression(arrow)) if arrow.expression
+                ) =>
+            {
+                self.returned_receivers(parent, &mut receivers)
+            }
+            _ => {}
+        }
+
+        self.iteration_receivers(value_id, &mut receivers);
+
+        receivers
+    }
+
+    /// The parent of the value at `value_id`, out of its parentheses
+    fn value_parent(&self, value_id: NodeId) -> Option<&'s AstNode<'a>> {
+        self.semantic
+            .nodes()
+            .ancestors(value_id)
+            .skip(1)
+            .find(|ancestor| !matches!(ancestor.kind(), AstKind::ParenthesizedExpression(_)))
+    }
+
+    /// The parameter of a function of this file receiving the argument, or the
+    /// collection storing it, e.g. `handlers` of `handlers.push(handler)`
+    fn argument_receivers(&self, argument: &AstNode, receivers: &mut Vec<Receiver>) {
+        let nodes = self.semantic.nodes();
+        let (callee, arguments) = match nodes.parent_kind(argument.id()) {
+            Some(AstKind::CallExpression(call_expression)) => {
+                (&call_expression.callee, &call_expression.arguments)
+            }
+            Some(AstKind::NewExpression(new_expression)) => {
+                (&new_expression.callee, &new_expression.arguments)
+            }
+            _ => return,
+        };
+        let Some(position) = arguments
+            .iter()
+            .position(|other| other.span() == argument.kind().span())
+        else {
+            return;
+        };
+
+        match callee.without_parentheses() {
+            // run(handler)
+            Expression::Identifier(identifier_reference) => {
+                let symbol_id = self
+                    .semantic
+                    .scoping()
+                    .get_reference(identifier_reference.reference_id())
+                    .symbol_id();
+                let parameters =
+                    symbol_id.and_then(|symbol_id| self.function_parameters(symbol_id));
+
+                receivers.extend(parameters.and_then(|parameters| {
+                    self.parameter_receiver(parameters, position, Flow::Argument)
+                }));
+            }
+            // handlers.push(handler)
+            callee => {
+                let Some(member_expression) = callee.as_member_expression() else {
+                    return;
+                };
+                let Expression::Identifier(identifier_reference) = member_expression.object()
+                else {
+                    return;
+                };
+
+                if !member_expression
+                    .static_property_name()
+                    .is_some_and(|name| STORE_METHODS.contains(&name))
+                {
+                    return;
+                }
+
+                let scoping = self.semantic.scoping();
+                let reference = scoping.get_reference(identifier_reference.reference_id());
+
+                if let Some(symbol_id) = reference.symbol_id() {
+                    receivers.push(Receiver {
+                        symbol_id,
+                        flow: Flow::Stored,
+                        line: self.line(scoping.symbol_span(symbol_id).start),
+                    });
+                }
+            }
+        }
+    }
+
+    /// The variables initialized with a call of the function returning the value, e.g.
+    /// `handler` of `const handler = createHandler()`
+    fn returned_receivers(&self, statement: &AstNode, receivers: &mut Vec<Receiver>) {
+        let nodes = self.semantic.nodes();
+        let Some(function) = nodes
+            .ancestors(statement.id())
+            .find(|ancestor| ancestor.kind().is_function_like())
+        else {
+            return;
+        };
+
+        let symbol_id = match (function.kind(), self.value_parent(function.id())) {
+            (AstKind::Function(function), _) if function.id.is_some() => {
+                function.id.as_ref().map(|id| id.symbol_id())
+            }
+            (_, Some(parent)) => match parent.kind() {
+                AstKind::VariableDeclarator(vd) => {
+                    vd.id.get_binding_identifier().map(|id| id.symbol_id())
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        let Some(symbol_id) = symbol_id else {
+            return;
+        };
+
+        for reference in self.semantic.symbol_references(symbol_id) {
+            let Some(call) = self.value_parent(reference.node_id()) else {
+                continue;
+            };
+            let AstKind::CallExpression(call_expression) = call.kind() else {
+                continue;
+            };
+
+            if call_expression.callee.without_parentheses().span()
+                != nodes.kind(reference.node_id()).span()
+            {
+                continue;
+            }
+
+            if let Some(AstKind::VariableDeclarator(vd)) =
+                self.value_parent(call.id()).map(AstNode::kind)
+            {
+                receivers.extend(self.binding_receiver(&vd.id, Flow::Returned));
+            }
+        }
+    }
+
+    /// The parameter of the callback iterating over the value, directly or through
+    /// `Object.values` or `Array.from`, and the variable of a `for...of` loop over it
+    fn iteration_receivers(&self, value_id: NodeId, receivers: &mut Vec<Receiver>) {
+        let nodes = self.semantic.nodes();
+
+        // handlers.forEach((fn) => fn()), whose last member access is the method
+        if nodes.kind(value_id).as_member_expression().is_some() {
+            receivers.extend(self.callback_receiver(nodes.get_node(value_id)));
+            return;
+        }
+
+        let mut iterated = value_id;
+
+        // Object.values(handlers).forEach((fn) => fn())
+        if let Some(argument) = self.value_parent(iterated) {
+            if let (AstKind::Argument(_), Some(call)) =
+                (argument.kind(), nodes.parent_node(argument.id()))
+            {
+                if let AstKind::CallExpression(call_expression) = call.kind() {
+                    let callee = call_expression.callee.get_member_expr();
+                    let is_copy = callee.is_some_and(|member_expression| {
+                        matches!(
+                            (
+                                member_expression.object().get_identifier_reference(),
+                                member_expression.static_property_name()
+                            ),
+                            (Some(object), Some(property))
+                                if matches!((object.name.as_str(), property), ("Object", "values") | ("Array", "from"))
+                        )
+                    });
+
+                    if is_copy {
+                        iterated = call.id();
+                    }
+                }
+            }
+        }
+
+        let Some(parent) = self.value_parent(iterated) else {
+            return;
+        };
+        let iterated_span = nodes.kind(iterated).span();
+
+        match parent.kind() {
+            AstKind::MemberExpression(member_expression)
+                if member_expression.object().span() == iterated_span =>
+            {
+                receivers.extend(self.callback_receiver(parent));
+            }
+            // for (const handler of handlers)
+            AstKind::ForOfStatement(for_of_statement)
+                if for_of_statement.right.span() == iterated_span =>
+            {
+                if let ForStatementLeft::VariableDeclaration(variable_declaration) =
+                    &for_of_statement.left
+                {
+                    for vd in &variable_declaration.declarations {
+                        receivers.extend(self.binding_receiver(&vd.id, Flow::Iterated));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The parameter receiving the elements in the callback of an iteration method
+    /// called as `member`, e.g. `fn` of `handlers.forEach((fn) => fn())`
+    fn callback_receiver(&self, member: &AstNode) -> Option<Receiver> {
+        let method = member
+            .kind()
+            .as_member_expression()?
+            .static_property_name()
+            .filter(|method| ITERATION_METHODS.contains(method))?;
+        let AstKind::CallExpression(call_expression) = self.value_parent(member.id())?.kind()
+        else {
+            return None;
+        };
+
+        if call_expression.callee.without_parentheses().span() != member.kind().span() {
+            return None;
+        }
+
+        let parameters = match call_expression
+            .arguments
+            .first()?
+            .as_expression()?
+            .without_parentheses()
+        {
+            Expression::FunctionExpression(function) => &function.params,
+            Expression::ArrowFunctionExpression(arrow) => &arrow.params,
+            _ => return None,
+        };
+        // the accumulator comes first
+        let position = usize::from(method.starts_with("reduce"));
+
+        self.parameter_receiver(parameters, position, Flow::Iterated)
+    }
+
+    /// The parameters of the function of this file declared as `symbol_id`
+    fn function_parameters(&self, symbol_id: SymbolId) -> Option<&'s FormalParameters<'a>> {
+        let nodes = self.semantic.nodes();
+        let declaration = self.semantic.scoping().symbol_declaration(symbol_id);
+
+        match nodes.kind(declaration) {
+            AstKind::Function(function) => Some(&function.params),
+            AstKind::VariableDeclarator(vd) => match vd.init.as_ref()?.without_parentheses() {
+                Expression::FunctionExpression(function) => Some(&function.params),
+                Expression::ArrowFunctionExpression(arrow) => Some(&arrow.params),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn parameter_receiver(
+        &self,
+        parameters: &FormalParameters,
+        position: usize,
+        flow: Flow,
+    ) -> Option<Receiver> {
+        self.binding_receiver(&parameters.items.get(position)?.pattern, flow)
+    }
+
+    /// A receiver for the binding of `pattern`, unless it's destructured
+    fn binding_receiver(&self, pattern: &BindingPattern, flow: Flow) -> Option<Receiver> {
+        let BindingPatternKind::BindingIdentifier(binding_identifier) = &pattern.kind else {
+            return None;
+        };
+
+        Some(Receiver {
+            symbol_id: binding_identifier.symbol_id(),
+            flow,
+            line: self.line(binding_identifier.span.start),
+        })
+    }
+
+    /// How the reference at `node_id` uses what it references, from its flags and from
+    /// the parent of `object_id`, its outermost static member access
+    fn reference_kind(&self, node_id: NodeId, object_id: NodeId) -> ReferenceKind {
+        let nodes = self.semantic.nodes();
+
+        if let AstKind::IdentifierReference(identifier_reference) = nodes.kind(node_id) {
+            let flags = self
+                .semantic
+                .scoping()
+                .get_reference(identifier_reference.reference_id())
+                .flags();
+
+            if flags.is_type_only() {
+                return ReferenceKind::Type;
+            }
+
+            if flags.is_write() {
+                return ReferenceKind::Write;
+            }
+        }
+
+        let parent_kind = nodes
+            .ancestors(object_id)
+            .skip(1)
+            .map(|ancestor| ancestor.kind())
+            .find(|kind| !matches!(kind, AstKind::ParenthesizedExpression(_)));
+
+        // arguments have a node of their own, so a call parent means a callee
+        match parent_kind {
+            Some(AstKind::CallExpression(_) | AstKind::NewExpression(_)) => ReferenceKind::Call,
+            Some(AstKind::Argument(_)) => ReferenceKind::Argument,
+            Some(AstKind::UnaryExpression(unary_expression))
+                if unary_expression.operator == UnaryOperator::Typeof =>
+            {
+                ReferenceKind::Typeof
+            }
+            Some(AstKind::ExportSpecifier(_) | AstKind::ExportDefaultDeclaration(_)) => {
+                ReferenceKind::Export
+            }
+            // order.total = 1
+            Some(
+                AstKind::SimpleAssignmentTarget(_)
+                | AstKind::AssignmentTarget(_)
+                | AstKind::UpdateExpression(_),
+            ) => ReferenceKind::Write,
+            _ => ReferenceKind::Read,
+        }
+    }
+
+    /// String literals and template literal quasis. Module specifiers of imports and
+    /// exports are not string constants.
+    fn strings(&self) -> Vec<StringSite> {
+        let nodes = self.semantic.nodes();
+        let mut strings = Vec::new();
+
+        for node in nodes.iter() {
+            match node.kind() {
+                AstKind::StringLiteral(string_literal) if !self.is_module_specifier(node) => {
+                    strings.push(StringSite {
+                        value: string_literal.value.to_string(),
+                        site: self.site(node.id()),
+                    });
+                }
+                AstKind::TemplateLiteral(template_literal) => {
+                    for cooked in template_literal
+                        .quasis
+                        .iter()
+                        .filter_map(|quasi| quasi.value.cooked)
+                    {
+                        strings.push(StringSite {
+                            value: cooked.to_string(),
+                            site: self.site(node.id()),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        strings
+    }
+
+    /// Whether the string literal `node` is the specifier of a module, e.g. of
+    /// `import("./cart.js")` or `require("lodash")`, rather than a constant
+    fn is_module_specifier(&self, node: &AstNode) -> bool {
+        let nodes = self.semantic.nodes();
+
+        match nodes.parent_kind(node.id()) {
+            Some(
+                AstKind::ImportDeclaration(_)
+                | AstKind::ExportNamedDeclaration(_)
+                | AstKind::ExportAllDeclaration(_)
+                | AstKind::ImportExpression(_),
+            ) => true,
+            Some(AstKind::Argument(_)) => {
+                let call = nodes.ancestors(node.id()).nth(2).map(AstNode::kind);
+
+                matches!(
+                    call,
+                    Some(AstKind::CallExpression(call_expression))
+                        if call_expression.callee_name() == Some("require")
+                            && call_expression.arguments.first().map(GetSpan::span)
+                                == Some(node.span())
+                )
+            }
+            _ => false,
+        }
+    }
+
+    /// Calls of this file matching the event `patterns`
+    fn event_sites(&self, patterns: &[EventPattern]) -> Vec<EventSite> {
+        let nodes = self.semantic.nodes();
+
+        nodes
+            .iter()
+            .filter_map(|node| {
+                let call_expression = node.kind().as_call_expression()?;
+                let callee_name = call_expression.callee_name()?;
+                let pattern = patterns
+                    .iter()
+                    .find(|pattern| pattern.call == callee_name)?;
+                let key = match &pattern.key {
+                    Some(key) => key.clone(),
+                    None => event_key(call_expression.arguments.first()?)?,
+                };
+
+                let handler = match pattern.role {
+                    EventRole::Emit => None,
+                    EventRole::Listen => call_expression
+                        .arguments
+                        .get(1)
+                        .and_then(Argument::as_expression)
+                        .and_then(|handler| self.handler(handler)),
+                };
+
+                let span = call_expression.span;
+                let line = self.line(span.start);
+
+                Some(EventSite {
+                    key,
+                    role: pattern.role,
+                    span,
+                    line,
+                    handler,
+                })
+            })
+            .collect()
+    }
+
+    /// What the `handler` argument of a listener subscribes, e.g. `sendReceipt` or
+    /// `(order) => {}`
+    fn handler(&self, handler: &Expression) -> Option<Handler> {
+        let scoping = self.semantic.scoping();
+
+        // a function is found through the scope it creates
+        let scope_id = match handler.without_parentheses() {
+            Expression::Identifier(identifier_reference) => {
+                return scoping
+                    .get_reference(identifier_reference.reference_id())
+                    .symbol_id()
+                    .map(Handler::Symbol);
+            }
+            Expression::FunctionExpression(function) => function.scope_id(),
+            Expression::ArrowFunctionExpression(arrow) => arrow.scope_id(),
+            _ => return None,
+        };
+
+        Some(Handler::Inline(self.site(scoping.get_node_id(scope_id))))
+    }
+
+    fn line(&self, offset: u32) -> usize {
+        self.line_starts.partition_point(|start| *start <= offset)
+    }
+
+    /// The imports and exports of this file, for the linker. `require` calls the
+    /// specifier can't be read from become diagnostics.
+    fn module_interface(
+        &self,
+        resolve: impl Fn(&str) -> Option<PathBuf>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> ModuleInterface {
+        let scoping = self.semantic.scoping();
+        let mut interface = ModuleInterface::default();
+
+        // `export { call }` and `export default call` reference the exported binding
+        let referenced_symbol_id = |name: &ModuleExportName| match name {
+            ModuleExportName::IdentifierReference(identifier_reference) => scoping
+                .get_reference(identifier_reference.reference_id())
+                .symbol_id(),
+            _ => None,
+        };
+
+        for node in self.semantic.nodes().iter() {
+            match node.kind() {
+                AstKind::ImportDeclaration(import_declaration) => {
+                    let specifier = import_declaration.source.value.as_str();
+                    let source = resolve(specifier);
+
+                    for import_specifier in import_declaration.specifiers.iter().flatten() {
+                        let name = match import_specifier {
+                            ImportDeclarationSpecifier::ImportSpecifier(import_specifier) => {
+                                ImportName::Named(import_specifier.imported.name().into())
+                            }
+                            ImportDeclarationSpecifier::ImportDefaultSpecifier(_) => {
+                                ImportName::Default
+                            }
+                            ImportDeclarationSpecifier::ImportNamespaceSpecifier(_) => {
+                                ImportName::Namespace
+                            }
+                        };
+
+                        interface.imports.push(ImportBinding {
+                            symbol_id: import_specifier.local().symbol_id(),
+                            specifier: specifier.into(),
+                            is_require: false,
+                            source: source.clone(),
+                            name,
+                        });
+                    }
+                }
+                AstKind::VariableDeclarator(vd) => {
+                    let specifier = match check_require(vd) {
+                        Some(Ok(specifier)) => specifier,
+                        Some(Err(error)) => {
+                            let line = self.line(vd.span.start);
+                            diagnostics.push(Diagnostic::new(Some(line), &error));
+                            continue;
+                        }
+                        None => continue,
+                    };
+                    let source = resolve(&specifier);
+
+                    match &vd.id.kind {
+                        BindingPatternKind::BindingIdentifier(binding_identifier) => {
+                            interface.imports.push(ImportBinding {
+                                symbol_id: binding_identifier.symbol_id(),
+                                specifier: specifier.into(),
+                                is_require: true,
+                                source,
+                                name: ImportName::Namespace,
+                            });
+                        }
+                        BindingPatternKind::ObjectPattern(object_pattern) => {
+                            for prop in object_pattern.properties.iter() {
+                                let Some(key) = prop.key.static_name() else {
+                                    continue;
+                                };
+                                let mut symbol_ids = Vec::new();
+                                get_symbol_ids_from_binding_pattern(&prop.value, &mut symbol_ids);
+
+                                interface
+                                    .imports
+                                    .extend(symbol_ids.into_iter().map(|symbol_id| {
+                                        ImportBinding {
+                                            symbol_id,
+                                            specifier: specifier.into(),
+                                            is_require: true,
+                                            source: source.clone(),
+                                            name: ImportName::Named(key.to_string()),
+                                        }
+                                    }));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                AstKind::ExportNamedDeclaration(export_declaration) => {
+                    let mut symbol_ids = Vec::new();
+
+                    match &export_declaration.declaration {
+                        Some(Declaration::VariableDeclaration(variable_declaration)) => {
+                            for vd in variable_declaration.declarations.iter() {
+                                get_symbol_ids_from_variable_declarator(vd, &mut symbol_ids);
+                            }
+                        }
+                        Some(Declaration::FunctionDeclaration(function)) => {
+                            symbol_ids.extend(function.id.as_ref().map(|id| id.symbol_id()));
+                        }
+                        Some(Declaration::ClassDeclaration(class)) => {
+                            symbol_ids.extend(class.id.as_ref().map(|id| id.symbol_id()));
+                        }
+                        _ => {}
+                    }
+
+                    for symbol_id in symbol_ids {
+                        interface.exports.insert(
+                            scoping.symbol_name(symbol_id).into(),
+                            ExportTarget::Local(symbol_id),
+                        );
+                    }
+
+                    let source = export_declaration
+                        .source
+                        .as_ref()
+                        .map(|source| resolve(&source.value));
+
+                    for specifier in export_declaration.specifiers.iter() {
+                        let target = match &source {
+                            Some(source) => ExportTarget::ReExport {
+                                source: source.clone(),
+                                name: match specifier.local.name().as_str() {
+                                    "default" => ImportName::Default,
+                                    name => ImportName::Named(name.into()),
+                                },
+                            },
+                            None => match referenced_symbol_id(&specifier.local) {
+                                Some(symbol_id) => ExportTarget::Local(symbol_id),
+                                None => continue,
+                            },
+                        };
+
+                        interface
+                            .exports
+                            .insert(specifier.exported.name().into(), target);
+                    }
+                }
+                AstKind::ExportDefaultDeclaration(export_declaration) => {
+                    let symbol_id = match &export_declaration.declaration {
+                        ExportDefaultDeclarationKind::FunctionDeclaration(function) => {
+                            function.id.as_ref().map(|id| id.symbol_id())
+                        }
+                        ExportDefaultDeclarationKind::ClassDeclaration(class) => {
+                            class.id.as_ref().map(|id| id.symbol_id())
+                        }
+                        ExportDefaultDeclarationKind::Identifier(identifier_reference) => scoping
+                            .get_reference(identifier_reference.reference_id())
+                            .symbol_id(),
+                        _ => None,
+                    };
+
+                    if let Some(symbol_id) = symbol_id {
+                        interface
+                            .exports
+                            .insert("default".into(), ExportTarget::Local(symbol_id));
+                    }
+                }
+                AstKind::ExportAllDeclaration(export_declaration) => {
+                    let source = resolve(&export_declaration.source.value);
+
+                    match &export_declaration.exported {
+                        Some(exported) => {
+                            interface.exports.insert(
+                                exported.name().into(),
+                                ExportTarget::ReExport {
+                                    source,
+                                    name: ImportName::Namespace,
+                                },
+                            );
+                        }
+                        None => interface.star_exports.extend(source),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        interface
+    }
+
+    /// The `require` calls of this file that don't initialize a variable, which the
+    /// module interface doesn't hold, with their specifiers resolved
+    fn inline_requires(&self, resolve: impl Fn(&str) -> Option<PathBuf>) -> Vec<InlineRequire> {
+        let nodes = self.semantic.nodes();
+        let mut inline_requires = Vec::new();
+
+        for node in nodes.iter() {
+            let AstKind::CallExpression(call_expression) = node.kind() else {
+                continue;
+            };
+
+            if call_expression.callee_name() != Some("require") {
+                continue;
+            }
+
+            let Some(Argument::StringLiteral(string_literal)) = call_expression.arguments.first()
+            else {
+                continue;
+            };
+
+            if let Some(AstKind::VariableDeclarator(vd)) = nodes.parent_kind(node.id()) {
+                if check_require(vd).is_some() {
+                    continue;
+                }
+            }
+
+            inline_requires.push(InlineRequire {
+                specifier: string_literal.value.to_string(),
+                source: resolve(&string_literal.value),
+                reference: self.reference_site(node.id()),
+            });
+        }
+
+        inline_requires
+    }
+}
+
+/// The specifier of `vd` when it's initialized with a `require` call, an error when the
+/// specifier isn't a string, e.g. `require(name)`
+fn check_require<'a>(vd: &VariableDeclarator<'a>) -> Option<Result<Atom<'a>, BumblebeeError>> {
+    let Some(Expression::CallExpression(exp)) = &vd.init else {
+        return None;
+    };
+
+    if exp.callee_name() != Some("require") {
+        return None;
+    }
+
+    match exp.arguments.first() {
+        Some(Argument::StringLiteral(sl)) => Some(Ok(sl.value)),
+        _ => Some(Err(BumblebeeError::MalformedRequire)),
+    }
+}
+
+/// Key of an event passed as `argument`: `"order:paid"`, `` `order:paid` `` or the first
+/// argument of `new CustomEvent("order:paid")`
+fn event_key(argument: &Argument) -> Option<String> {
+    match argument.as_expression()? {
+        Expression::StringLiteral(string_literal) => Some(string_literal.value.to_string()),
+        Expression::TemplateLiteral(template_literal) => {
+            template_literal.quasi().map(|quasi| quasi.to_string())
+        }
+        Expression::NewExpression(new_expression) => event_key(new_expression.arguments.first()?),
+        _ => None,
+    }
+}
+
+fn get_symbol_ids_from_binding_pattern(
+    binding_pattern: &BindingPattern,
+    symbol_ids: &mut Vec<SymbolId>,
+) {
+    match &binding_pattern.kind {
+        BindingPatternKind::BindingIdentifier(binding_indentifier) => {
+            symbol_ids.push(binding_indentifier.symbol_id());
+        }
+        BindingPatternKind::ObjectPattern(object_pattern) => {
+            get_symbol_ids_from_object_pattern(object_pattern, symbol_ids)
+        }
+        BindingPatternKind::ArrayPattern(array_pattern) => {
+            get_symbol_ids_from_array_pattern(array_pattern, symbol_ids)
+        }
+        BindingPatternKind::AssignmentPattern(_) => {}
+    }
+}
+
+fn get_symbol_ids_from_array_pattern(array_pattern: &ArrayPattern, symbol_ids: &mut Vec<SymbolId>) {
+    for element in array_pattern.elements.iter().flatten() {
+        get_symbol_ids_from_binding_pattern(element, symbol_ids);
+    }
+}
+
+fn get_symbol_ids_from_object_pattern(
+    object_pattern: &ObjectPattern,
+    symbol_ids: &mut Vec<SymbolId>,
+) {
+    for prop in object_pattern.properties.iter() {
+        get_symbol_ids_from_binding_pattern(&prop.value, symbol_ids);
+    }
+}
+
+/// Whether `expression` calls a function right away, e.g. `(() => {})()`
+fn is_iife(expression: &Expression) -> bool {
+    let Expression::CallExpression(call_expression) = expression.without_parentheses() else {
+        return false;
+    };
+
+    matches!(
+        call_expression.callee.without_parentheses(),
+        Expression::FunctionExpression(_) | Expression::ArrowFunctionExpression(_)
+    )
+}
+
+/// `default`, or the name of the function or class exported by default
+fn default_export_name(export_declaration: &ExportDefaultDeclaration) -> String {
+    let id = match &export_declaration.declaration {
+        ExportDefaultDeclarationKind::FunctionDeclaration(function) => function.id.as_ref(),
+        ExportDefaultDeclarationKind::ClassDeclaration(class) => class.id.as_ref(),
+        _ => None,
+    };
+
+    id.map_or_else(|| "default".into(), |id| id.name.to_string())
+}
+
+/// Whether a node is reported on its own at `granularity`, once widened by [`unit_node`]
+fn is_unit(kind: AstKind, parent_kind: Option<AstKind>, granularity: Granularity) -> bool {
+    let is_top_level = matches!(parent_kind, Some(AstKind::Program(_)));
+
+    match granularity {
+        Granularity::Statement => {
+            is_top_level
+                || kind.is_statement()
+                || (kind.is_declaration() && !matches!(kind, AstKind::PropertyDefinition(_)))
+        }
+        Granularity::Function => is_top_level || kind.is_function_like(),
+        Granularity::TopLevel => is_top_level,
+        Granularity::File => matches!(kind, AstKind::Program(_)),
+    }
+}
+
+/// The node a unit is reported as: a function together with the method, property or
+/// variable it's the value of or the call running it right away, and a declaration
+/// together with its `export` or the loop it's the head of
+fn unit_node(nodes: &AstNodes, node_id: NodeId) -> NodeId {
+    let mut node_id = node_id;
+
+    while let Some(parent) = nodes.parent_node(node_id) {
+        let kind = nodes.kind(node_id);
+        let is_wrapped = match parent.kind() {
+            AstKind::MethodDefinition(_)
+            | AstKind::PropertyDefinition(_)
+            | AstKind::ObjectProperty(_)
+            | AstKind::VariableDeclarator(_) => kind.is_function_like(),
+            AstKind::VariableDeclaration(_) => matches!(kind, AstKind::VariableDeclarator(_)),
+            // (() => {})();
+            AstKind::ParenthesizedExpression(_) | AstKind::CallExpression(_) => {
+                kind.is_function_like() || matches!(kind, AstKind::ParenthesizedExpression(_))
+            }
+            AstKind::ExpressionStatement(_) => matches!(kind, AstKind::CallExpression(_)),
+            AstKind::ForStatementInit(_)
+            | AstKind::ForStatement(_)
+            | AstKind::ForInStatement(_)
+            | AstKind::ForOfStatement(_) => matches!(
+                kind,
+                AstKind::VariableDeclaration(_) | AstKind::ForStatementInit(_)
+            ),
+            AstKind::ExportNamedDeclaration(_)
+            | AstKind::ExportDefaultDeclaration(_)
+            | AstKind::ModuleDeclaration(_) => true,
+            _ => false,
+        };
+
+        if !is_wrapped {
+            break;
+        }
+
+        node_id = parent.id();
+    }
+
+    node_id
+}
+
+fn get_symbol_ids_from_variable_declarator(
+    node: &VariableDeclarator,
+    symbol_ids: &mut Vec<SymbolId>,
+) {
+    get_symbol_ids_from_binding_pattern(&node.id, symbol_ids);
+}
+
+/// `SymbolId` and `Span` don't implement `Deserialize`, so they're stored as integers
+pub(crate) mod serde_ids {
+    pub mod symbol_id {
+        use oxc_index::Idx;
+        use oxc_semantic::SymbolId;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        pub fn serialize<S: Serializer>(
+            symbol_id: &SymbolId,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            (symbol_id.index() as u32).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<SymbolId, D::Error> {
+            u32::deserialize(deserializer).map(SymbolId::new)
+        }
+    }
+
+    pub mod symbol_ids {
+        use oxc_index::Idx;
+        use oxc_semantic::SymbolId;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        pub fn serialize<S: Serializer>(
+            symbol_ids: &[SymbolId],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            let indexes: Vec<u32> = symbol_ids.iter().map(|id| id.index() as u32).collect();
+            indexes.serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<SymbolId>, D::Error> {
+            let indexes = Vec::<u32>::deserialize(deserializer)?;
+            Ok(indexes.into_iter().map(SymbolId::new).collect())
+        }
+    }
+
+    pub mod span {
+        use oxc_span::Span;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        pub fn serialize<S: Serializer>(span: &Span, serializer: S) -> Result<S::Ok, S::Error> {
+            (span.start, span.end).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Span, D::Error> {
+            let (start, end) = <(u32, u32)>::deserialize(deserializer)?;
+            Ok(Span::new(start, end))
+        }
+    }
+
+    pub mod spans {
+        use oxc_span::Span;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        pub fn serialize<S: Serializer>(spans: &[Span], serializer: S) -> Result<S::Ok, S::Error> {
+            let offsets: Vec<(u32, u32)> =
+                spans.iter().map(|span| (span.start, span.end)).collect();
+            offsets.serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<Span>, D::Error> {
+            let offsets = Vec::<(u32, u32)>::deserialize(deserializer)?;
+            Ok(offsets
+                .into_iter()
+                .map(|(start, end)| Span::new(start, end))
+                .collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module::ParsedModule;
+    use std::path::Path;
+
+    #[test]
+    fn units_of_granularity() {
+        let source_text = "export const main = () => {\n  const ready = call();\n};\n";
+        let module = ParsedModule::parse(Path::new("index.js"), source_text.into()).unwrap();
+        let units = |granularity| {
+            let index = module
+                .with_semantic(|semantic| FileIndex::build(semantic, &[], granularity, |_| None));
+            let call = &index.unresolved_references["call"][0].site;
+
+            (
+                index.units[call.unit.unwrap()].span,
+                index.statements[call.statement.unwrap()],
+            )
+        };
+        let text = |span: Span| &source_text[span.start as usize..span.end as usize];
+
+        let (statement, top_level) = units(Granularity::Statement);
+        assert_eq!(text(statement), "const ready = call();");
+        assert_eq!(text(units(Granularity::Function).0), text(top_level));
+        assert_eq!(text(units(Granularity::TopLevel).0), text(top_level));
+        assert_eq!(
+            units(Granularity::File).0,
+            Span::new(0, source_text.len() as u32)
+        );
+    }
+
+    #[test]
+    fn unit_names() {
+        let source_text = "export const orders = {\n  place(order) {\n    order.items.forEach((item) => send(item));\n  },\n};\n(function () {\n  send();\n})();\nconst { send } = {\n  send: (item) => {},\n  drop: () => {},\n};\nexport const run = () => [1].map((x) => x).filter((y) => y).forEach((z) => z);\n";
+        let module = ParsedModule::parse(Path::new("orders.js"), source_text.into()).unwrap();
+        let index = module.with_semantic(|semantic| {
+            FileIndex::build(semantic, &[], Granularity::Function, |_| None)
+        });
+        let names: Vec<&str> = index.units.iter().map(|unit| unit.name.as_str()).collect();
+
+        assert_eq!(
+            names,
+            [
+                "orders",
+                "orders.place",
+                "orders.place.<callback@L3>",
+                "<iife@L6>",
+                // the destructuring, and the value of the property it takes `send` from
+                "send",
+                "send@L10:3",
+                "drop",
+                // callbacks chained on one line
+                "run",
+                "run.<callback@L13>",
+                "run.<callback@L13>@L13:51",
+                "run.<callback@L13>@L13:69"
+            ]
+        );
+    }
+
+    #[test]
+    fn module_specifiers_are_not_strings() {
+        let source_text = "import { flags } from \"./flags.js\";\nconst cart = import(\"./cart.js\");\nconst _ = require(\"lodash\");\nflags.isEnabled(\"checkout.v2\", require(\"./config.js\").env);\n";
+        let module = ParsedModule::parse(Path::new("app.js"), source_text.into()).unwrap();
+        let index = module.with_semantic(|semantic| {
+            FileIndex::build(semantic, &[], Granularity::TopLevel, |_| None)
+        });
+        let values: Vec<&str> = index
+            .strings
+            .iter()
+            .map(|string| string.value.as_str())
+            .collect();
+
+        assert_eq!(values, ["checkout.v2"]);
+    }
+
+    #[test]
+    fn reference_kinds() {
+        let source_text = "let total = 0;\ntotal();\ntotal = 1;\nlog(total);\nconsole.log(typeof total, total.value);\ntotal.value += 1;\nexport { total };\n";
+        let module = ParsedModule::parse(Path::new("total.js"), source_text.into()).unwrap();
+        let index = module.with_semantic(|semantic| {
+            FileIndex::build(semantic, &[], Granularity::TopLevel, |_| None)
+        });
+        let total = index.symbol(index.symbol_id("total").unwrap());
+        let kinds: Vec<ReferenceKind> = total
+            .references
+            .iter()
+            .map(|reference| reference.kind)
+            .collect();
+
+        assert_eq!(
+            kinds,
+            [
+                ReferenceKind::Call,
+                ReferenceKind::Write,
+                ReferenceKind::Argument,
+                ReferenceKind::Typeof,
+                ReferenceKind::Argument,
+                ReferenceKind::Write,
+                ReferenceKind::Export,
+            ]
+        );
+    }
+
+    #[test]
+    fn receivers() {
+        let source_text = "function handler() {}\nfunction run(callback) {\n  callback();\n}\nrun(handler);\nconst handlers = [handler];\nhandlers.forEach((fn) => fn());\nfor (const each of Object.values(handlers)) {}\n";
+        let module = ParsedModule::parse(Path::new("run.js"), source_text.into()).unwrap();
+        let index = module.with_semantic(|semantic| {
+            FileIndex::build(semantic, &[], Granularity::TopLevel, |_| None)
+        });
+        let receivers = |name: &str| -> Vec<(String, Flow)> {
+            index
+                .symbol(index.symbol_id(name).unwrap())
+                .references
+                .iter()
+                .flat_map(|reference| &reference.receivers)
+                .map(|receiver| {
+                    (
+                        index.symbol(receiver.symbol_id).name.to_string(),
+                        receiver.flow,
+                    )
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            receivers("handler"),
+            [
+                ("callback".to_string(), Flow::Argument),
+                ("handlers".to_string(), Flow::Stored),
+            ]
+        );
+        assert_eq!(
+            receivers("handlers"),
+            [
+                ("fn".to_string(), Flow::Iterated),
+                ("each".to_string(), Flow::Iterated),
+            ]
+        );
+    }
+}
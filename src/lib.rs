@@ -1,4 +1,17 @@
+pub mod cache;
 pub mod cli;
+pub mod config;
 pub mod core;
+pub mod diagnostics;
+pub mod error;
+pub mod graph;
+pub mod index;
+pub mod linker;
+pub mod module;
+pub mod prefilter;
 pub mod query;
+pub mod report;
+pub mod resolver;
 pub mod service;
+#[cfg(test)]
+mod testing;
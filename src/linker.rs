@@ -0,0 +1,315 @@
+use crate::index::serde_ids;
+use oxc_semantic::SymbolId;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+/// A symbol of the project. `SymbolId`s are only meaningful within the file they come
+/// from, so the file is part of the key.
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct SymbolKey {
+    file: PathBuf,
+    symbol_id: SymbolId,
+}
+
+impl SymbolKey {
+    pub fn new(file: PathBuf, symbol_id: SymbolId) -> Self {
+        Self { file, symbol_id }
+    }
+
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn symbol_id(&self) -> SymbolId {
+        self.symbol_id
+    }
+}
+
+/// What an import binding takes from the imported module
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub enum ImportName {
+    /// `import { call }` or `const { call } = require()`
+    Named(String),
+    /// `import call`
+    Default,
+    /// `import * as factory` or `const factory = require()`
+    Namespace,
+}
+
+impl ImportName {
+    fn export_name(&self) -> Option<&str> {
+        match self {
+            Self::Named(name) => Some(name),
+            Self::Default => Some("default"),
+            Self::Namespace => None,
+        }
+    }
+}
+
+/// A binding of a file holding something imported or required from another module
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ImportBinding {
+    #[serde(with = "serde_ids::symbol_id")]
+    pub symbol_id: SymbolId,
+    /// The specifier as written, e.g. `lodash/debounce`
+    pub specifier: String,
+    /// Whether the binding is `require`d rather than imported
+    pub is_require: bool,
+    /// The imported file, `None` when the specifier doesn't resolve to a file
+    pub source: Option<PathBuf>,
+    pub name: ImportName,
+}
+
+/// What a module exports under a name
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum ExportTarget {
+    /// `export const call`, `export { call }` or `export default call`
+    Local(#[serde(with = "serde_ids::symbol_id")] SymbolId),
+    /// `export { call } from "./factory.js"` or `export * as factory from "./factory.js"`
+    ReExport {
+        source: Option<PathBuf>,
+        name: ImportName,
+    },
+}
+
+/// The imports and exports of a file, with their specifiers resolved
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ModuleInterface {
+    pub imports: Vec<ImportBinding>,
+    pub exports: HashMap<String, ExportTarget>,
+    /// Sources of the `export * from` declarations
+    pub star_exports: Vec<PathBuf>,
+}
+
+/// Where an import binding gets its value from
+#[derive(Clone)]
+enum LinkTarget {
+    Symbol(SymbolKey),
+    Namespace(PathBuf),
+}
+
+/// Links every import binding of the project to the symbol it imports, so the references
+/// of a symbol are followed into other files without resolving names or specifiers again
+#[derive(Default)]
+pub struct Linker {
+    /// Import bindings holding a symbol, by the symbol they hold
+    importers: HashMap<SymbolKey, Vec<SymbolKey>>,
+    /// Bindings holding a whole module, e.g. `import * as factory`, by the module
+    namespace_importers: HashMap<PathBuf, Vec<SymbolKey>>,
+    /// Names a symbol is exported under by its own file
+    export_names: HashMap<SymbolKey, Vec<String>>,
+    /// What each file was linked with, for it to be unlinked when it or the modules it
+    /// imports from change
+    links: HashMap<PathBuf, FileLinks>,
+}
+
+#[derive(Default)]
+struct FileLinks {
+    /// Symbols of the file it exports
+    exports: Vec<SymbolKey>,
+    /// Bindings of the file, with what they were linked to
+    imports: Vec<(SymbolKey, LinkTarget)>,
+}
+
+impl Linker {
+    pub fn build(interfaces: &HashMap<PathBuf, ModuleInterface>) -> Self {
+        let mut linker = Self::default();
+        linker.update(interfaces, interfaces.keys());
+
+        linker
+    }
+
+    /// Links the bindings of `files` again, e.g. after they or the modules they import
+    /// from changed, and unlinks the ones missing from `interfaces` since they were
+    /// deleted
+    pub fn update<'f>(
+        &mut self,
+        interfaces: &HashMap<PathBuf, ModuleInterface>,
+        files: impl IntoIterator<Item = &'f PathBuf>,
+    ) {
+        for file in files {
+            self.unlink(file);
+
+            if let Some(interface) = interfaces.get(file) {
+                self.link(interfaces, file, interface);
+            }
+        }
+    }
+
+    fn link(
+        &mut self,
+        interfaces: &HashMap<PathBuf, ModuleInterface>,
+        file: &Path,
+        interface: &ModuleInterface,
+    ) {
+        let mut links = FileLinks::default();
+
+        for (name, target) in &interface.exports {
+            if let ExportTarget::Local(symbol_id) = target {
+                let symbol = SymbolKey::new(file.to_owned(), *symbol_id);
+                self.export_names
+                    .entry(symbol.clone())
+                    .or_default()
+                    .push(name.to_owned());
+                links.exports.push(symbol);
+            }
+        }
+
+        for import in &interface.imports {
+            let binding = SymbolKey::new(file.to_owned(), import.symbol_id);
+            let Some(source) = &import.source else {
+                continue;
+            };
+            let Some(target) =
+                resolve_export(interfaces, source, &import.name, &mut HashSet::new())
+            else {
+                continue;
+            };
+
+            self.bindings(&target).push(binding.clone());
+            links.imports.push((binding, target));
+        }
+
+        self.links.insert(file.to_owned(), links);
+    }
+
+    fn unlink(&mut self, file: &Path) {
+        let Some(links) = self.links.remove(file) else {
+            return;
+        };
+
+        for symbol in links.exports {
+            self.export_names.remove(&symbol);
+        }
+
+        for (binding, target) in links.imports {
+            self.bindings(&target)
+                .retain(|importer| *importer != binding);
+        }
+    }
+
+    fn bindings(&mut self, target: &LinkTarget) -> &mut Vec<SymbolKey> {
+        match target {
+            LinkTarget::Symbol(symbol) => self.importers.entry(symbol.clone()).or_default(),
+            LinkTarget::Namespace(module) => {
+                self.namespace_importers.entry(module.clone()).or_default()
+            }
+        }
+    }
+
+    /// Import bindings of other files holding `symbol`
+    pub fn importers(&self, symbol: &SymbolKey) -> &[SymbolKey] {
+        self.importers.get(symbol).map_or(&[], Vec::as_slice)
+    }
+
+    /// Bindings of other files holding the whole module `file`
+    pub fn namespace_importers(&self, file: &Path) -> &[SymbolKey] {
+        self.namespace_importers
+            .get(file)
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Names `symbol` is exported under by its own file
+    pub fn export_names(&self, symbol: &SymbolKey) -> &[String] {
+        self.export_names.get(symbol).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Follows the re-exports of `file` to what it exports as `name`
+fn resolve_export(
+    interfaces: &HashMap<PathBuf, ModuleInterface>,
+    file: &Path,
+    name: &ImportName,
+    visited: &mut HashSet<PathBuf>,
+) -> Option<LinkTarget> {
+    let Some(export_name) = name.export_name() else {
+        return Some(LinkTarget::Namespace(file.to_path_buf()));
+    };
+
+    // re-export cycles don't export anything
+    if !visited.insert(file.to_path_buf()) {
+        return None;
+    }
+
+    let interface = interfaces.get(file)?;
+
+    match interface.exports.get(export_name) {
+        Some(ExportTarget::Local(symbol_id)) => Some(LinkTarget::Symbol(SymbolKey::new(
+            file.to_path_buf(),
+            *symbol_id,
+        ))),
+        Some(ExportTarget::ReExport { source, name }) => {
+            resolve_export(interfaces, source.as_deref()?, name, visited)
+        }
+        // `export *` never re-exports the default export
+        None if export_name == "default" => None,
+        None => interface
+            .star_exports
+            .iter()
+            .find_map(|source| resolve_export(interfaces, source, name, visited)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn link_through_re_exports() {
+        let factory = PathBuf::from("/factory.js");
+        let api = PathBuf::from("/api.js");
+        let app = PathBuf::from("/app.js");
+        let call = SymbolKey::new(factory.clone(), SymbolId::new(0));
+
+        let mut interfaces = HashMap::new();
+        let mut factory_interface = ModuleInterface::default();
+        factory_interface
+            .exports
+            .insert("call".into(), ExportTarget::Local(call.symbol_id()));
+        interfaces.insert(factory.clone(), factory_interface);
+        interfaces.insert(
+            api.clone(),
+            ModuleInterface {
+                star_exports: vec![factory.clone()],
+                ..Default::default()
+            },
+        );
+        interfaces.insert(
+            app.clone(),
+            ModuleInterface {
+                imports: vec![
+                    ImportBinding {
+                        symbol_id: SymbolId::new(0),
+                        specifier: "./api.js".into(),
+                        is_require: false,
+                        source: Some(api),
+                        name: ImportName::Named("call".into()),
+                    },
+                    ImportBinding {
+                        symbol_id: SymbolId::new(1),
+                        specifier: "./factory.js".into(),
+                        is_require: false,
+                        source: Some(factory.clone()),
+                        name: ImportName::Namespace,
+                    },
+                ],
+                ..Default::default()
+            },
+        );
+
+        let linker = Linker::build(&interfaces);
+
+        assert_eq!(
+            linker.importers(&call),
+            [SymbolKey::new(app.clone(), SymbolId::new(0))]
+        );
+        assert_eq!(
+            linker.namespace_importers(&factory),
+            [SymbolKey::new(app, SymbolId::new(1))]
+        );
+        assert_eq!(linker.export_names(&call), ["call"]);
+    }
+}
@@ -19,10 +19,38 @@ mod tests {
 
     #[test]
     fn main_test() {
+        let target_dir = tempfile::tempdir().unwrap();
         let args = Args {
             project_path: "test-dir".to_string(),
-            target_path: "output".to_string(),
+            target_path: Some(target_dir.path().to_string_lossy().into()),
+            no_cache: true,
+            ..Default::default()
         };
-        assert!(run(args).is_ok());
+        // logged through the output the test captures, before `run` logs to stderr
+        tracing_subscriber::fmt().with_test_writer().try_init().ok();
+        run(args).unwrap();
+
+        let mut impacted_files: Vec<String> = ignore::Walk::new(target_dir.path())
+            .flatten()
+            .filter(|entry| entry.path().is_file())
+            .map(|entry| {
+                let path = entry.path().strip_prefix(target_dir.path()).unwrap();
+                path.to_string_lossy().into_owned()
+            })
+            .collect();
+        impacted_files.sort();
+        let fellow = std::fs::read_to_string(target_dir.path().join("pkgs/utils/index.js"));
+
+        assert_eq!(
+            impacted_files,
+            [
+                "index.js",
+                "orders.js",
+                "pkgs/utils/index.js",
+                "receipts.js",
+                "shadow.js"
+            ]
+        );
+        assert!(fellow.unwrap().contains("fellow"));
     }
 }
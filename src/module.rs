@@ -0,0 +1,131 @@
+use crate::error::BumblebeeError;
+use oxc_allocator::Allocator;
+use oxc_parser::Parser;
+use oxc_semantic::{Semantic, SemanticBuilder};
+use oxc_span::SourceType;
+use self_cell::self_cell;
+use std::path::Path;
+
+/// What the AST and the semantic of a file borrow from
+struct ModuleSource {
+    allocator: Allocator,
+    source_text: String,
+    source_type: SourceType,
+}
+
+self_cell!(
+    struct ModuleCell {
+        owner: ModuleSource,
+
+        #[not_covariant]
+        dependent: Semantic,
+    }
+);
+
+/// A syntax error of a parsed file, which the AST is recovered around
+pub struct SyntaxError {
+    /// Byte offset of the error
+    pub offset: u32,
+    pub message: String,
+}
+
+/// A parsed file owning its own arena, so every thread parses into an arena of its own
+/// and the whole AST is freed at once when the module is dropped
+pub struct ParsedModule {
+    cell: ModuleCell,
+    syntax_errors: Vec<SyntaxError>,
+    /// Whether the parser gave up on the file, leaving the AST empty
+    panicked: bool,
+}
+
+impl ParsedModule {
+    pub fn parse(source_path: &Path, source_text: String) -> Result<Self, BumblebeeError> {
+        let source = ModuleSource {
+            allocator: Allocator::default(),
+            source_text,
+            source_type: SourceType::from_path(source_path)
+                .map_err(|_| BumblebeeError::UnsupportedFileType)?,
+        };
+
+        let mut syntax_errors = Vec::new();
+        let mut panicked = false;
+
+        let cell = ModuleCell::new(source, |source| {
+            let parser_return =
+                Parser::new(&source.allocator, &source.source_text, source.source_type).parse();
+
+            panicked = parser_return.panicked;
+            syntax_errors = parser_return
+                .errors
+                .iter()
+                .map(|error| SyntaxError {
+                    offset: error
+                        .labels
+                        .as_ref()
+                        .and_then(|labels| labels.first())
+                        .map_or(0, |label| label.offset() as u32),
+                    message: error.message.to_string(),
+                })
+                .collect();
+
+            let program = source.allocator.alloc(parser_return.program);
+
+            SemanticBuilder::new().build(program).semantic
+        });
+
+        Ok(Self {
+            cell,
+            syntax_errors,
+            panicked,
+        })
+    }
+
+    /// Runs `f` with the semantic of the file, which can't outlive the arena it points into
+    pub fn with_semantic<R>(&self, f: impl for<'a> FnOnce(&Semantic<'a>) -> R) -> R {
+        self.cell.with_dependent(|_, semantic| f(semantic))
+    }
+
+    pub fn source_text(&self) -> &str {
+        &self.cell.borrow_owner().source_text
+    }
+
+    pub fn syntax_errors(&self) -> &[SyntaxError] {
+        &self.syntax_errors
+    }
+
+    pub fn panicked(&self) -> bool {
+        self.panicked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rayon::prelude::*;
+
+    #[test]
+    fn parse_into_an_arena_per_thread() {
+        // the modules stay on the thread parsing them, only what's extracted is sent back
+        let parsed: Vec<(String, usize)> = (0..8)
+            .into_par_iter()
+            .map(|i| {
+                let source_text = format!("export const call{i} = () => {{}};\ncall{i}();\n");
+                let module = ParsedModule::parse(Path::new("factory.js"), source_text).unwrap();
+
+                module.with_semantic(|semantic| {
+                    let symbol_id = semantic.scoping().symbol_ids().next().unwrap();
+                    let name = semantic.scoping().symbol_name(symbol_id).to_string();
+
+                    (name, semantic.symbol_references(symbol_id).count())
+                })
+            })
+            .collect();
+
+        for (i, (name, references)) in parsed.into_iter().enumerate() {
+            assert_eq!(name, format!("call{i}"));
+            assert_eq!(references, 1);
+        }
+
+        assert!(ParsedModule::parse(Path::new("factory.txt"), String::new()).is_err());
+    }
+}
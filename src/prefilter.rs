@@ -0,0 +1,181 @@
+use crate::config::Config;
+use crate::resolver::package_path;
+use aho_corasick::AhoCorasick;
+use anyhow::Result;
+use rayon::prelude::*;
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+/// Files of the project that aren't indexed yet
+///
+/// Most files never mention what the queries are about, so a file is only parsed once
+/// its text contains one of the names the analysis looks for. The names grow with the
+/// impacted symbols, so the files are taken out gradually.
+#[derive(Default)]
+pub struct Prefilter {
+    pending: HashSet<PathBuf>,
+    /// Names the pending files were searched for already
+    patterns: HashSet<String>,
+}
+
+impl Prefilter {
+    /// Adds files to the pending ones, taking back right away the ones mentioning a name
+    /// searched for before
+    pub fn extend(&mut self, paths: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+        let patterns: Vec<&String> = self.patterns.iter().collect();
+        let (matching, pending) = partition_matching(paths, &patterns)?;
+
+        self.pending.extend(pending);
+
+        Ok(matching)
+    }
+
+    /// Takes the pending files mentioning any of `patterns` they weren't searched for yet
+    pub fn take_matching(
+        &mut self,
+        patterns: impl IntoIterator<Item = String>,
+    ) -> Result<Vec<PathBuf>> {
+        let patterns: Vec<String> = patterns
+            .into_iter()
+            .filter(|pattern| !pattern.is_empty() && self.patterns.insert(pattern.to_owned()))
+            .collect();
+
+        if patterns.is_empty() || self.pending.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let (matching, pending) = partition_matching(std::mem::take(&mut self.pending), &patterns)?;
+        self.pending = pending.into_iter().collect();
+
+        Ok(matching)
+    }
+
+    /// Forgets a file, e.g. because it was indexed or deleted
+    pub fn remove(&mut self, path: &Path) -> bool {
+        self.pending.remove(path)
+    }
+}
+
+/// Splits `paths` into the files containing any of `patterns` and the others. A file that
+/// can't be read counts as matching, for the error to surface when it's indexed.
+fn partition_matching(
+    paths: impl IntoIterator<Item = PathBuf>,
+    patterns: &[impl AsRef<[u8]>],
+) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let paths: Vec<PathBuf> = paths.into_iter().collect();
+
+    if patterns.is_empty() {
+        return Ok((Vec::new(), paths));
+    }
+
+    let matcher = AhoCorasick::new(patterns)?;
+
+    Ok(paths
+        .into_par_iter()
+        .partition(|path| std::fs::read(path).map_or(true, |contents| matcher.is_match(&contents))))
+}
+
+/// Endings of the specifiers naming a directory by its path alone, e.g. `"."` or `"../"`,
+/// which import its index file without any name
+const DIRECTORY_SPECIFIER_ENDS: [&str; 4] = [".\"", ".'", "./\"", "./'"];
+
+/// Names a specifier importing `file` most likely contains: the name of the file, of its
+/// directory for an index file, of the package it belongs to, and the aliases pointing to
+/// it or to one of its directories
+pub fn module_names(root_path: &Path, config: &Config, file: &Path) -> Vec<String> {
+    let mut names = Vec::new();
+    let name = |path: &Path| {
+        path.file_stem()
+            .map(|name| name.to_string_lossy().into_owned())
+    };
+
+    names.extend(name(file));
+
+    if names.first().is_some_and(|name| name == "index") {
+        names.extend(file.parent().and_then(name));
+        names.extend(DIRECTORY_SPECIFIER_ENDS.map(String::from));
+    }
+
+    // bare specifiers name the package, e.g. `@acme/ui` for `node_modules/@acme/ui/index.js`
+    names.extend(package_path(file).map(|(package, _)| package));
+
+    for (alias, targets) in &config.aliases {
+        if targets
+            .iter()
+            .any(|target| file.starts_with(root_path.join(target)))
+        {
+            names.push(alias.to_owned());
+        }
+    }
+
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::project;
+
+    #[test]
+    fn take_files_mentioning_patterns() {
+        let dir = project(&[
+            ("app.js", "import { call } from './factory.js';\n"),
+            ("other.js", "export const noop = () => {};\n"),
+        ]);
+        let (app, other) = (dir.path().join("app.js"), dir.path().join("other.js"));
+
+        let mut prefilter = Prefilter::default();
+        let extended = prefilter.extend(vec![app.clone(), other.clone()]).unwrap();
+        let matching = prefilter.take_matching(["factory".to_string()]).unwrap();
+        let searched_again = prefilter.take_matching(["factory".to_string()]).unwrap();
+
+        assert!(extended.is_empty());
+        assert_eq!(matching, [app]);
+        assert!(searched_again.is_empty());
+        assert!(prefilter.remove(&other));
+    }
+
+    #[test]
+    fn names_of_scoped_package() {
+        let root_path = Path::new("/app");
+        let file = root_path.join("node_modules/@acme/ui/dist/button.js");
+
+        assert_eq!(
+            module_names(root_path, &Config::default(), &file),
+            ["button", "@acme/ui"]
+        );
+    }
+
+    #[test]
+    fn take_files_importing_index_by_directory() {
+        let dir = project(&[
+            ("cart/index.js", "export const add = () => {};\n"),
+            ("cart/button.js", "import { add } from \".\";\n"),
+            ("cart/list/item.js", "import { add } from '../';\n"),
+            ("other.js", "export const noop = () => {};\n"),
+        ]);
+        let file = dir.path().join("cart/index.js");
+        let names = module_names(dir.path(), &Config::default(), &file);
+
+        let mut prefilter = Prefilter::default();
+        prefilter
+            .extend(
+                ["cart/button.js", "cart/list/item.js", "other.js"]
+                    .map(|path| dir.path().join(path))
+                    .to_vec(),
+            )
+            .unwrap();
+        let mut matching = prefilter.take_matching(names).unwrap();
+        matching.sort();
+
+        assert_eq!(
+            matching,
+            [
+                dir.path().join("cart/button.js"),
+                dir.path().join("cart/list/item.js")
+            ]
+        );
+    }
+}
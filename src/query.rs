@@ -1,42 +1,51 @@
-use oxc_semantic::SymbolId;
+use crate::config::QueryConfig;
+use crate::resolver::package_path;
+use anyhow::Result;
 use std::{
-    hash::{Hash, Hasher},
-    path::PathBuf,
+    collections::HashMap,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    process::Command,
 };
 
+/// Where an analysis starts from
 #[derive(PartialEq, Eq, Clone, Debug)]
-pub struct Query {
-    symbol: String, // e.g. call() symbol
-    symbol_id: Option<SymbolId>,
-    symbol_path: PathBuf, // from ./factory.js file
+pub enum Query {
+    Symbol(SymbolQuery),
+    Package(PackageQuery),
+    Global(GlobalQuery),
+    Env(EnvQuery),
+    String(StringQuery),
+    Diff(DiffQuery),
 }
 
-impl Hash for Query {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        if let Some(symbol_id) = self.symbol_id {
-            symbol_id.hash(state);
-        } else {
-            self.symbol.hash(state);
+impl From<&QueryConfig> for Query {
+    fn from(query: &QueryConfig) -> Self {
+        match query {
+            QueryConfig::Symbol { symbol, path } => {
+                Self::Symbol(SymbolQuery::new_with_symbol(symbol.clone(), path.clone()))
+            }
+            QueryConfig::Package { package, symbol } => {
+                Self::Package(PackageQuery::new(package.clone(), symbol.clone()))
+            }
+            QueryConfig::Global { global } => Self::Global(GlobalQuery::new(global)),
+            QueryConfig::Env { env } => Self::Env(EnvQuery::new(env.clone())),
+            QueryConfig::String { string } => Self::String(StringQuery::new(string.clone())),
         }
-
-        self.symbol_path.hash(state);
     }
 }
 
-impl Query {
-    #[allow(dead_code)]
-    pub fn new(symbol_id: SymbolId, symbol_name: String, symbol_path: PathBuf) -> Self {
-        Self {
-            symbol: symbol_name,
-            symbol_id: Some(symbol_id),
-            symbol_path,
-        }
-    }
+/// A symbol of our code to start the analysis from, by its name and the file declaring it
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct SymbolQuery {
+    symbol: String,       // e.g. call() symbol
+    symbol_path: PathBuf, // from ./factory.js file
+}
 
+impl SymbolQuery {
     pub fn new_with_symbol(symbol: String, symbol_path: PathBuf) -> Self {
         Self {
             symbol,
-            symbol_id: None,
             symbol_path,
         }
     }
@@ -49,3 +58,260 @@ impl Query {
         &self.symbol
     }
 }
+
+/// A symbol of a third-party package, e.g. `debounce` of `lodash`
+///
+/// The package itself is never parsed, its imports in our code are where the impact starts.
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct PackageQuery {
+    package: String,
+    symbol: String,
+    /// The file the package itself resolves to from the root of the project, `None` when
+    /// it isn't installed
+    entry: Option<PathBuf>,
+}
+
+impl PackageQuery {
+    pub fn new(package: String, symbol: String) -> Self {
+        Self {
+            package,
+            symbol,
+            entry: None,
+        }
+    }
+
+    /// The query with the file the package itself resolves to
+    pub fn with_entry(self, entry: Option<PathBuf>) -> Self {
+        Self { entry, ..self }
+    }
+
+    pub fn entry(&self) -> Option<&Path> {
+        self.entry.as_deref()
+    }
+
+    pub fn package(&self) -> &String {
+        &self.package
+    }
+
+    pub fn symbol(&self) -> &String {
+        &self.symbol
+    }
+
+    /// Where an import of `specifier`, resolved to `source`, points into the package:
+    /// `Some("")` for the package itself, `Some("debounce")` for `lodash/debounce` and
+    /// `None` for any other module
+    ///
+    /// The file the import resolves to tells, so aliases of the package match too. The
+    /// specifier is only matched as written when it doesn't resolve to a file, e.g. for a
+    /// package that isn't installed or a Node built-in, which matches with and without
+    /// the `node:` prefix.
+    pub fn subpath(&self, specifier: &str, source: Option<&Path>) -> Option<String> {
+        let Some(source) = source else {
+            return self.specifier_subpath(specifier).map(String::from);
+        };
+
+        let (package, path) = package_path(source)?;
+
+        if package != self.package {
+            return None;
+        }
+
+        if self.entry() == Some(source) {
+            return Some(String::new());
+        }
+
+        // the exports of the package may map its subpaths to other files
+        if let Some(subpath) = self.specifier_subpath(specifier) {
+            return Some(subpath.into());
+        }
+
+        // lodash/debounce.js or lodash/debounce/index.js
+        let mut path = path.with_extension("");
+
+        if path.ends_with("index") {
+            path.pop();
+        }
+
+        Some(
+            path.iter()
+                .map(|component| component.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+        )
+    }
+
+    fn specifier_subpath<'s>(&self, specifier: &'s str) -> Option<&'s str> {
+        let package = self.package.strip_prefix("node:").unwrap_or(&self.package);
+        let specifier = specifier.strip_prefix("node:").unwrap_or(specifier);
+        let subpath = specifier.strip_prefix(package)?;
+
+        if subpath.is_empty() {
+            Some(subpath)
+        } else {
+            subpath.strip_prefix('/')
+        }
+    }
+}
+
+/// Objects whose members are globals themselves, e.g. `window.fetch` is `fetch`
+pub const GLOBAL_OBJECTS: [&str; 4] = ["globalThis", "window", "self", "global"];
+
+/// A global that has no declaration in our code, e.g. `fetch`, `process.env` or
+/// `window.localStorage.setItem`
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct GlobalQuery {
+    members: Vec<String>,
+}
+
+impl GlobalQuery {
+    pub fn new(path: &str) -> Self {
+        let mut members: Vec<String> = path.split('.').map(String::from).collect();
+
+        if members.len() > 1 && GLOBAL_OBJECTS.contains(&members[0].as_str()) {
+            members.remove(0);
+        }
+
+        Self { members }
+    }
+
+    /// The member path of the global, without a leading global object
+    pub fn members(&self) -> &[String] {
+        &self.members
+    }
+}
+
+/// An environment variable, read through `process.env` or `import.meta.env`
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct EnvQuery {
+    name: String,
+}
+
+impl EnvQuery {
+    pub fn new(name: String) -> Self {
+        Self { name }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+}
+
+/// A string constant coupling code together, e.g. a feature flag, a translation key or an
+/// action type
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub struct StringQuery {
+    value: String,
+}
+
+impl StringQuery {
+    pub fn new(value: String) -> Self {
+        Self { value }
+    }
+
+    pub fn value(&self) -> &String {
+        &self.value
+    }
+}
+
+/// The lines changed since the last commit, whose code is where the impact starts
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct DiffQuery {
+    /// Changed lines of the new contents, 1-based, by path relative to the project
+    files: HashMap<PathBuf, Vec<RangeInclusive<usize>>>,
+}
+
+impl DiffQuery {
+    /// The uncommitted changes of the tracked files under `root_path`
+    pub fn uncommitted(root_path: &Path) -> Result<Self> {
+        let output = Command::new("git")
+            .args([
+                "diff",
+                "--unified=0",
+                "--no-color",
+                "--no-ext-diff",
+                "--relative",
+            ])
+            .args(["--src-prefix=a/", "--dst-prefix=b/", "HEAD", "--", "."])
+            .current_dir(root_path)
+            .output()?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Unable to diff {}: {}",
+                root_path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(Self::parse(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    /// Reads the changed lines of a `git diff --unified=0`
+    pub fn parse(diff: &str) -> Self {
+        let mut files: HashMap<PathBuf, Vec<RangeInclusive<usize>>> = HashMap::new();
+        // deleted files have no new contents to be impacted
+        let mut file = None;
+
+        for line in diff.lines() {
+            if let Some(path) = line.strip_prefix("+++ ") {
+                file = path.strip_prefix("b/").map(PathBuf::from);
+                continue;
+            }
+
+            // @@ -12,3 +12,4 @@
+            let (Some(hunk), Some(file)) = (line.strip_prefix("@@ "), &file) else {
+                continue;
+            };
+            let Some(range) = hunk.split(' ').find_map(|range| range.strip_prefix('+')) else {
+                continue;
+            };
+            let (start, count) = range.split_once(',').unwrap_or((range, "1"));
+            let (Ok(start), Ok(count)) = (start.parse::<usize>(), count.parse::<usize>()) else {
+                continue;
+            };
+
+            // lines removed without replacement touch the line they were after
+            let start = start.max(1);
+            let end = start + count.max(1) - 1;
+
+            files.entry(file.clone()).or_default().push(start..=end);
+        }
+
+        Self { files }
+    }
+
+    /// Paths of the changed files, relative to the project
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files.keys()
+    }
+
+    /// Changed lines of the file at `relative_path`
+    pub fn lines(&self, relative_path: &Path) -> &[RangeInclusive<usize>] {
+        self.files.get(relative_path).map_or(&[], Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_diff() {
+        let query = DiffQuery::parse(
+            "diff --git a/orders.js b/orders.js\n\
+             --- a/orders.js\n\
+             +++ b/orders.js\n\
+             @@ -3 +3 @@ export function placeOrder(order) {\n\
+             -  return order;\n\
+             +  return { ...order };\n\
+             @@ -10,2 +9,0 @@\n\
+             diff --git a/old.js b/old.js\n\
+             --- a/old.js\n\
+             +++ /dev/null\n\
+             @@ -1,4 +0,0 @@\n",
+        );
+
+        assert_eq!(query.lines(Path::new("orders.js")), [3..=3, 9..=9]);
+        assert!(query.lines(Path::new("old.js")).is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+use crate::config::ReferenceKind;
+use crate::core::Edge;
+use crate::diagnostics::Diagnostic;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// The impacted areas found by an analysis, with paths relative to the project
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImpactReport {
+    /// Impacted units, ordered by file and position
+    pub units: Vec<ImpactedUnit>,
+    pub edges: Vec<Edge>,
+    /// Entry points from the config that contain impacted code
+    pub entry_points: Vec<PathBuf>,
+    /// Problems of the files, which are analyzed without the code the problems are about
+    pub diagnostics: Vec<FileDiagnostic>,
+    /// Files whose analysis may miss impacted code
+    pub incomplete: Vec<PathBuf>,
+}
+
+/// A piece of code containing impacted references
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct ImpactedUnit {
+    /// Identifier of the unit that stays the same between runs, e.g.
+    /// `src/orders.js#checkout.place`, or just the path for a whole file
+    pub id: String,
+    pub path: PathBuf,
+    /// First line of the unit, 1-based
+    pub start_line: usize,
+    /// Last line of the unit, 1-based
+    pub end_line: usize,
+    /// How the impacted references in the unit use what they reference, empty for a
+    /// unit impacted otherwise, e.g. by a change
+    pub reference_kinds: Vec<ReferenceKind>,
+    pub text: String,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct FileDiagnostic {
+    pub path: PathBuf,
+    #[serde(flatten)]
+    pub diagnostic: Diagnostic,
+}
+
+impl ImpactReport {
+    /// Impacted units grouped by file
+    pub fn files(&self) -> impl Iterator<Item = (&Path, &[ImpactedUnit])> {
+        self.units
+            .chunk_by(|unit, next| unit.path == next.path)
+            .map(|units| (units[0].path.as_path(), units))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::query::{Query, SymbolQuery};
+    use crate::testing::{analyze, project};
+    use serde_json::json;
+
+    #[test]
+    fn serialize_report() {
+        let dir = project(&[
+            ("cart.js", "export const add = () => {};\n"),
+            (
+                "checkout.js",
+                "import { add } from \"./cart.js\";\nimport { pay } from \"./pay.js\";\nexport const buy = () => add();\n",
+            ),
+        ]);
+        let query = SymbolQuery::new_with_symbol("add".into(), "cart.js".into());
+        let report = analyze(dir.path(), &[Query::Symbol(query)]);
+
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            json!({
+                "units": [
+                    {
+                        "id": "cart.js#add",
+                        "path": "cart.js",
+                        "start_line": 1,
+                        "end_line": 1,
+                        "reference_kinds": [],
+                        "text": "export const add = () => {};"
+                    },
+                    {
+                        "id": "checkout.js#<import@L1>",
+                        "path": "checkout.js",
+                        "start_line": 1,
+                        "end_line": 1,
+                        "reference_kinds": [],
+                        "text": "import { add } from \"./cart.js\";"
+                    },
+                    {
+                        "id": "checkout.js#buy",
+                        "path": "checkout.js",
+                        "start_line": 3,
+                        "end_line": 3,
+                        "reference_kinds": ["call"],
+                        "text": "export const buy = () => add();"
+                    }
+                ],
+                "edges": [],
+                "entry_points": [],
+                "diagnostics": [
+                    {
+                        "path": "checkout.js",
+                        "line": null,
+                        "message": "Unresolved import `./pay.js`: Cannot find module './pay.js'"
+                    }
+                ],
+                "incomplete": []
+            })
+        );
+    }
+}
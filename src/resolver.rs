@@ -0,0 +1,171 @@
+use crate::config::Config;
+use crate::error::BumblebeeError;
+use oxc_resolver::{
+    AliasValue, ResolveError, ResolveOptions, Resolver, TsconfigOptions, TsconfigReferences,
+};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+/// Resolves the import specifiers of the project, built once for its config
+///
+/// Every `(directory, specifier)` pair is only resolved once, since the files of a
+/// directory usually import the same modules.
+pub struct ModuleResolver {
+    resolver: Resolver,
+    resolved: RwLock<HashMap<(PathBuf, String), Result<PathBuf, ResolveError>>>,
+}
+
+impl ModuleResolver {
+    pub fn new(root_path: &Path, config: &Config) -> Self {
+        let tsconfig = ["tsconfig.json", "jsconfig.json"]
+            .into_iter()
+            .map(|name| root_path.join(name))
+            .find(|config_path| config_path.exists())
+            .map(|config_file| TsconfigOptions {
+                config_file,
+                references: TsconfigReferences::Auto,
+            });
+
+        let alias = config
+            .aliases
+            .iter()
+            .map(|(key, paths)| {
+                let values = paths
+                    .iter()
+                    .map(|path| AliasValue::Path(root_path.join(path).to_string_lossy().into()))
+                    .collect();
+
+                (key.to_owned(), values)
+            })
+            .collect();
+
+        let options = ResolveOptions {
+            extensions: config
+                .extensions
+                .iter()
+                .map(|extension| format!(".{extension}"))
+                .collect(),
+            extension_alias: vec![(".js".into(), vec![".ts".into(), ".js".into()])],
+            condition_names: config.conditions.clone(),
+            alias,
+            tsconfig,
+            // `fs` or `node:fs` aren't files, but aren't a problem either
+            builtin_modules: true,
+            ..ResolveOptions::default()
+        };
+
+        Self {
+            resolver: Resolver::new(options),
+            resolved: Default::default(),
+        }
+    }
+
+    /// Resolves `specifier` as imported from `source_path`
+    pub fn resolve(&self, source_path: &Path, specifier: &str) -> Result<PathBuf, ResolveError> {
+        let directory = source_path.parent().unwrap_or(source_path);
+        let key = (directory.to_path_buf(), specifier.to_owned());
+
+        if let Some(resolved) = self.resolved.read().unwrap().get(&key) {
+            return resolved.clone();
+        }
+
+        let resolved = self
+            .resolver
+            .resolve(directory, specifier)
+            .map(|resolution| resolution.full_path());
+
+        self.resolved
+            .write()
+            .unwrap()
+            .entry(key)
+            .or_insert(resolved)
+            .clone()
+    }
+
+    /// Why `specifier` doesn't resolve as imported from `source_path`, unless it does or
+    /// is a built-in module of Node
+    pub fn unresolved(&self, source_path: &Path, specifier: &str) -> Option<BumblebeeError> {
+        match self.resolve(source_path, specifier) {
+            Ok(_) | Err(ResolveError::Builtin { .. }) => None,
+            Err(error) => Some(BumblebeeError::UnresolvedImport {
+                specifier: specifier.into(),
+                reason: error.to_string(),
+            }),
+        }
+    }
+
+    /// Forgets what was resolved, e.g. because files were added or deleted since
+    pub fn clear(&self) {
+        self.resolved.write().unwrap().clear();
+        self.resolver.clear_cache();
+    }
+}
+
+/// The package of `node_modules` the file at `path` belongs to, and the path of the file
+/// within it, e.g. `@acme/ui` and `button/index.js` for `node_modules/@acme/ui/button/index.js`
+pub fn package_path(path: &Path) -> Option<(String, PathBuf)> {
+    let components: Vec<&OsStr> = path.iter().collect();
+    // the innermost package, e.g. of `node_modules/.pnpm/lodash@4/node_modules/lodash`
+    let start = components
+        .iter()
+        .rposition(|component| *component == "node_modules")?
+        + 1;
+    let scope = components.get(start)?.to_string_lossy();
+    let (name, rest) = if scope.starts_with('@') {
+        let name = components.get(start + 1)?.to_string_lossy();
+        (format!("{scope}/{name}"), start + 2)
+    } else {
+        (scope.into_owned(), start + 1)
+    };
+
+    Some((name, components[rest..].iter().collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::project;
+
+    #[test]
+    fn resolve_once_per_directory() {
+        let dir = project(&[("src/cart.js", ""), ("src/app.js", ""), ("src/list.js", "")]);
+        let resolver = ModuleResolver::new(dir.path(), &Config::default());
+        let (app, list) = (
+            dir.path().join("src/app.js"),
+            dir.path().join("src/list.js"),
+        );
+
+        let resolved = resolver.resolve(&app, "./cart").unwrap();
+        std::fs::remove_file(&resolved).unwrap();
+        // a file of the same directory gets the memoized resolution
+        let memoized = resolver.resolve(&list, "./cart");
+        resolver.clear();
+        let cleared = resolver.resolve(&list, "./cart");
+
+        assert_eq!(resolved, dir.path().join("src/cart.js"));
+        assert_eq!(memoized, Ok(resolved));
+        assert!(cleared.is_err());
+    }
+
+    #[test]
+    fn unresolved_imports_but_builtins() {
+        let dir = project(&[("app.js", "")]);
+        let resolver = ModuleResolver::new(dir.path(), &Config::default());
+        let app = dir.path().join("app.js");
+
+        let missing = resolver
+            .unresolved(&app, "./missing")
+            .map(|error| error.to_string());
+
+        assert_eq!(
+            missing.as_deref(),
+            Some("Unresolved import `./missing`: Cannot find module './missing'")
+        );
+        assert!(resolver.unresolved(&app, "node:fs").is_none());
+        assert!(resolver.unresolved(&app, "fs").is_none());
+    }
+}
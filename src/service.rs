@@ -1,372 +1,608 @@
-use crate::query::Query;
+use crate::cache::IndexCache;
+use crate::config::{Config, ReferenceKind};
+use crate::diagnostics::Diagnostic;
+use crate::error::BumblebeeError;
+use crate::index::{EventSite, FileIndex, Handler, Receiver, ReferenceSite, Site};
+use crate::linker::{ImportName, Linker, ModuleInterface, SymbolKey};
+use crate::module::ParsedModule;
+use crate::query::{EnvQuery, GlobalQuery, PackageQuery, StringQuery, GLOBAL_OBJECTS};
+use crate::resolver::ModuleResolver;
 use anyhow::Result;
-use oxc_ast::{
-    ast::{
-        Argument, ArrayPattern, BindingPattern, BindingPatternKind, Expression, ObjectPattern,
-        VariableDeclarator,
-    },
-    AstKind,
+use oxc_semantic::SymbolId;
+use oxc_span::Span;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    ops::RangeInclusive,
+    path::PathBuf,
 };
-use oxc_resolver::TsconfigOptions;
-use oxc_resolver::{ResolveOptions, Resolver};
-use oxc_semantic::{AstNode, NodeId, Reference, Semantic, SymbolId};
-use oxc_span::Atom;
-use std::{collections::HashSet, path::PathBuf};
-
-fn resolve_import_path(root_path: &PathBuf, specifier: &str) -> Result<PathBuf> {
-    let tsconfig_path = root_path.join("tsconfig.json");
-    let jsconfig_path = root_path.join("jsconfig.json");
-    let config_path = if tsconfig_path.exists() {
-        Some(tsconfig_path)
-    } else if jsconfig_path.exists() {
-        Some(jsconfig_path)
-    } else {
-        None
-    };
-
-    let mut tsconfig_options = None;
-
-    if let Some(config_path) = config_path {
-        tsconfig_options = Some(TsconfigOptions {
-            config_file: config_path,
-            references: oxc_resolver::TsconfigReferences::Auto,
-        });
+use xxhash_rust::xxh3::xxh3_64;
+
+/// The impacted code of a file
+#[derive(Default)]
+struct Impact {
+    /// Indexes of the impacted top-level statements of the file, with the fewest hops
+    /// they are away from the queried symbols
+    statements: HashMap<usize, usize>,
+    /// Hops the code being marked as impacted is away from the queried symbols
+    depth: usize,
+    /// Indexes of the impacted units of the file, with the kinds of the impacted
+    /// references in them
+    units: HashMap<usize, BTreeSet<ReferenceKind>>,
+    /// Symbols of the file whose references are impacted in turn
+    symbol_ids: HashSet<SymbolId>,
+    /// Bindings the impacted values flow into without referencing them, with the line
+    /// of the reference they flow from
+    flows: Vec<(usize, Receiver)>,
+}
+
+impl Impact {
+    /// Marks the code enclosing `site` as impacted, with the symbols declared on the way
+    fn add_site(&mut self, index: &FileIndex, site: &Site) {
+        debug_symbol_names(index, site);
+        self.add_statement(site.statement);
+        self.add_unit(site.unit);
+        self.symbol_ids.extend(site.symbols.iter().copied());
     }
 
-    let options = ResolveOptions {
-        extensions: vec![".js".into()],
-        extension_alias: vec![(".js".into(), vec![".ts".into(), ".js".into()])],
-        condition_names: vec!["node".into(), "import".into(), "require".into()],
-        tsconfig: tsconfig_options,
-        ..ResolveOptions::default()
-    };
+    /// Marks the code enclosing `reference` as impacted. Unless `config` follows its
+    /// kind, the code is only reported and doesn't impact more code.
+    fn add_reference(&mut self, index: &FileIndex, reference: &ReferenceSite, config: &Config) {
+        if config.follows(reference.kind) {
+            self.add_site(index, &reference.site);
 
-    let full_path = Resolver::new(options)
-        .resolve(root_path, specifier)?
-        .full_path();
+            for receiver in &reference.receivers {
+                self.symbol_ids.insert(receiver.symbol_id);
 
-    Ok(full_path)
-}
+                // a receiver impacted already still gets the edge of the value it holds
+                let is_known = self.flows.iter().any(|(line, known)| {
+                    *line == reference.line
+                        && known.symbol_id == receiver.symbol_id
+                        && known.flow == receiver.flow
+                });
 
-fn check_require<'a>(node: &'a AstNode) -> Option<Atom<'a>> {
-    let vd = node.kind().as_variable_declarator();
-    let mut specifier = None;
-
-    if let Some(vd) = vd {
-        if let Some(Expression::CallExpression(exp)) = &vd.init {
-            if exp.callee_name().unwrap() == "require" {
-                // we assume that require will always have exactly 1 arguemnt
-                if let Argument::StringLiteral(sl) = &exp.arguments[0] {
-                    specifier = Some(sl.value);
+                if !is_known {
+                    self.flows.push((reference.line, receiver.clone()));
                 }
             }
+        } else {
+            self.add_unit(reference.site.unit);
         }
-    }
-
-    specifier
-}
 
-fn check_import(root_path: &PathBuf, node: &AstNode, semantic: &Semantic) -> Option<PathBuf> {
-    let nodes = semantic.nodes();
-    let mut import_node = None;
+        if let Some(unit) = reference.site.unit {
+            self.units.entry(unit).or_default().insert(reference.kind);
+        }
+    }
 
-    for ancestor in nodes.ancestors(node.id()) {
-        match ancestor.kind() {
-            AstKind::Program(_) => {
-                break;
-            }
-            AstKind::ModuleDeclaration(oxc_ast::ast::ModuleDeclaration::ImportDeclaration(id)) => {
-                import_node = Some(id.source.value);
-                break;
-            }
-            AstKind::VariableDeclarator(_) => {
-                import_node = check_require(ancestor);
+    fn add_statement(&mut self, statement: Option<usize>) {
+        if let Some(statement) = statement {
+            let depth = self.statements.entry(statement).or_insert(self.depth);
+            *depth = self.depth.min(*depth);
+        }
+    }
 
-                if import_node.is_some() {
-                    break;
-                }
-            }
-            _ => {}
+    fn add_unit(&mut self, unit: Option<usize>) {
+        if let Some(unit) = unit {
+            self.units.entry(unit).or_default();
         }
     }
 
-    // I somehow also need to keep track of what symbol were there in the import
-    // or I can assume that we're finding reference of only 1 symbol at a time
-    // and so there will never be the case when we reach require or import where
-    // that symbol was not referred
-    if let Some(specifier) = import_node {
-        return Some(resolve_import_path(root_path, specifier.into()).unwrap());
+    /// Marks the declaration of `symbol_id` as impacted. Only the symbol itself is
+    /// followed, not the other bindings of the same declaration.
+    fn add_declaration(&mut self, index: &FileIndex, symbol_id: SymbolId) {
+        let declaration = &index.symbol(symbol_id).declaration;
+
+        debug_symbol_names(index, declaration);
+        self.add_statement(declaration.statement);
+        self.add_unit(declaration.unit);
     }
+}
 
-    None
+fn debug_symbol_names(index: &FileIndex, site: &Site) {
+    site.symbols.iter().for_each(|x| {
+        let symbol_name = &index.symbol(*x).name;
+        tracing::trace!(symbol = %symbol_name, "impacted symbol");
+    });
 }
 
-fn get_symbol_ids_from_binding_pattern(
-    binding_pattern: &BindingPattern,
-    symbol_ids: &mut Vec<SymbolId>,
-) {
-    match &binding_pattern.kind {
-        BindingPatternKind::BindingIdentifier(binding_indentifier) => {
-            symbol_ids.push(binding_indentifier.symbol_id());
-        }
-        BindingPatternKind::ObjectPattern(object_pattern) => {
-            get_symbol_ids_from_object_pattern(object_pattern, symbol_ids)
-        }
-        BindingPatternKind::ArrayPattern(array_pattern) => {
-            get_symbol_ids_from_array_pattern(array_pattern, symbol_ids)
-        }
-        BindingPatternKind::AssignmentPattern(_) => {}
-    }
+/// A file of the project together with the code of it the queries impact
+pub struct ServiceReference {
+    service: Service,
+    impact: Impact,
 }
 
-fn get_symbol_ids_from_array_pattern(array_pattern: &ArrayPattern, symbol_ids: &mut Vec<SymbolId>) {
-    for element in array_pattern.elements.iter().flatten() {
-        get_symbol_ids_from_binding_pattern(element, symbol_ids);
+impl ServiceReference {
+    pub fn new(service: Service) -> Self {
+        Self {
+            service,
+            impact: Impact::default(),
+        }
     }
-}
 
-fn get_symbol_ids_from_object_pattern(
-    object_pattern: &ObjectPattern,
-    symbol_ids: &mut Vec<SymbolId>,
-) {
-    for prop in object_pattern.properties.iter() {
-        get_symbol_ids_from_binding_pattern(&prop.value, symbol_ids);
+    /// Finds the references of the queried symbol in this file: the declaration itself
+    /// when it lives here, otherwise the bindings the linker bound to it. Same-named
+    /// bindings of other scopes or modules are not the queried symbol and are left out.
+    ///
+    /// The symbol is `depth` hops away from the queried ones.
+    pub fn find_references(
+        &mut self,
+        symbol: &SymbolKey,
+        depth: usize,
+        linker: &Linker,
+        config: &Config,
+    ) {
+        let (index, impact) = (self.service.index(), &mut self.impact);
+        impact.depth = depth;
+        let source_path = self.service.source_path.as_path();
+
+        tracing::debug!("finding references");
+
+        let symbol_ids: Vec<SymbolId> = if symbol.file() == source_path {
+            vec![symbol.symbol_id()]
+        } else {
+            linker
+                .importers(symbol)
+                .iter()
+                .filter(|binding| binding.file() == source_path)
+                .map(|binding| binding.symbol_id())
+                .collect()
+        };
+
+        // the other bindings of the declaration, e.g. `DEBUG` of
+        // `const { API_URL, DEBUG } = process.env`, aren't impacted by it
+        for id in symbol_ids {
+            impact.add_declaration(index, id);
+            impact.symbol_ids.insert(id);
+
+            for reference in &index.symbol(id).references {
+                impact.add_reference(index, reference, config);
+            }
+        }
+
+        // `import * as factory` only references the symbol through `factory.call`
+        let namespace_symbol_ids = linker
+            .namespace_importers(symbol.file())
+            .iter()
+            .filter(|binding| binding.file() == source_path)
+            .map(|binding| binding.symbol_id());
+
+        for namespace_symbol_id in namespace_symbol_ids {
+            let mut is_referenced = false;
+
+            for reference in &index.symbol(namespace_symbol_id).references {
+                for export_name in linker.export_names(symbol) {
+                    if reference.members.first() == Some(export_name) {
+                        impact.add_reference(index, reference, config);
+                        is_referenced = true;
+                    }
+                }
+            }
+
+            if is_referenced {
+                impact.add_declaration(index, namespace_symbol_id);
+            }
+        }
     }
-}
 
-fn get_symbol_ids_from_variable_declarator(
-    node: &VariableDeclarator,
-    symbol_ids: &mut Vec<SymbolId>,
-) {
-    get_symbol_ids_from_binding_pattern(&node.id, symbol_ids);
-}
+    /// Finds the bindings of `query` imported or required from the package. They become
+    /// impacted symbols of this file, and so the roots of the impact in our code.
+    pub fn find_package_references(&mut self, query: &PackageQuery, config: &Config) {
+        let (index, impact) = (self.service.index(), &mut self.impact);
+        // bindings holding the queried symbol itself
+        let mut root_symbol_ids = Vec::new();
+        // bindings holding the whole package, e.g. `_` of `import _ from "lodash"`
+        let mut package_symbol_ids = Vec::new();
+
+        for import in &index.interface.imports {
+            let Some(subpath) = query.subpath(&import.specifier, import.source.as_deref()) else {
+                continue;
+            };
+
+            match &import.name {
+                ImportName::Named(name) if subpath.is_empty() && name == query.symbol() => {
+                    root_symbol_ids.push(import.symbol_id)
+                }
+                ImportName::Named(_) if subpath.is_empty() => {}
+                // `const { debounce } = require("lodash/debounce")` is a property of it
+                ImportName::Named(_) if import.is_require => {}
+                _ if subpath.is_empty() => package_symbol_ids.push(import.symbol_id),
+                _ if subpath == *query.symbol() => root_symbol_ids.push(import.symbol_id),
+                _ => {}
+            }
+        }
 
-fn debug_ast_node(node: &AstNode, semantic: &Semantic) -> (Option<NodeId>, Vec<SymbolId>) {
-    let nodes = semantic.nodes();
-    let mut answer = (None, Vec::new());
+        for symbol_id in root_symbol_ids {
+            impact.add_declaration(index, symbol_id);
+            impact.symbol_ids.insert(symbol_id);
+        }
 
-    for ancestor in nodes.ancestors(node.id()) {
-        match ancestor.kind() {
-            AstKind::Program(_) => {}
-            AstKind::Function(func) => {
-                if let Some(id) = &func.id {
-                    answer.1.push(id.symbol_id());
+        // only the `_.debounce` member accesses of a package binding are impacted
+        for symbol_id in package_symbol_ids {
+            let mut is_referenced = false;
+
+            for reference in &index.symbol(symbol_id).references {
+                if reference.members.first() == Some(query.symbol()) {
+                    impact.add_reference(index, reference, config);
+                    is_referenced = true;
                 }
-                answer.0 = Some(ancestor.id());
             }
-            AstKind::VariableDeclarator(vd) => {
-                get_symbol_ids_from_variable_declarator(vd, &mut answer.1);
-                answer.0 = Some(ancestor.id());
+
+            if is_referenced {
+                impact.add_declaration(index, symbol_id);
             }
-            _ => {
-                answer.0 = Some(ancestor.id());
+        }
+
+        // require("lodash").debounce(search) or require("lodash/debounce")(search)
+        for inline_require in &index.inline_requires {
+            let subpath =
+                query.subpath(&inline_require.specifier, inline_require.source.as_deref());
+            let reference = &inline_require.reference;
+            let is_referenced = match subpath.as_deref() {
+                Some("") => reference.members.first() == Some(query.symbol()),
+                Some(subpath) => subpath == query.symbol(),
+                None => false,
+            };
+
+            if is_referenced {
+                impact.add_reference(index, reference, config);
             }
         }
     }
 
-    answer.1.iter().for_each(|x| {
-        let symbol_name = semantic.scoping().symbol_name(*x);
-        println!("SymbolName: {}", symbol_name);
-    });
+    /// Finds the references of a global that has no declaration, i.e. the unresolved
+    /// references of this file, following the member path of `query`
+    pub fn find_global_references(&mut self, query: &GlobalQuery, config: &Config) {
+        let (index, impact) = (self.service.index(), &mut self.impact);
+
+        for (name, references) in &index.unresolved_references {
+            let members = if query.members()[0] == *name {
+                &query.members()[1..]
+            } else if GLOBAL_OBJECTS.contains(&name.as_str()) {
+                // `window.fetch` is the same `fetch` as a bare `fetch`
+                query.members()
+            } else {
+                continue;
+            };
+
+            for reference in references {
+                if reference.members.starts_with(members) {
+                    impact.add_reference(index, reference, config);
+                }
+            }
+        }
+    }
 
-    answer
-}
+    /// Finds the reads of an environment variable: `process.env.VAR`, `process.env["VAR"]`,
+    /// `import.meta.env.VAR` and the bindings destructured from either env object
+    pub fn find_env_references(&mut self, query: &EnvQuery, config: &Config) {
+        let (index, impact) = (self.service.index(), &mut self.impact);
+        let env_references = index
+            .unresolved_references
+            .get("process")
+            .into_iter()
+            .flatten()
+            .chain(&index.import_meta)
+            .filter(|reference| {
+                reference
+                    .members
+                    .first()
+                    .is_some_and(|member| member == "env")
+            });
+        let mut root_symbol_ids = Vec::new();
+
+        for reference in env_references {
+            if reference.members.get(1) == Some(query.name()) {
+                impact.add_reference(index, reference, config);
+                continue;
+            }
 
-pub struct ServiceReference<'a> {
-    service: &'a Service<'a>,
-    reference_node_ids: &'a mut HashSet<NodeId>,
-    reference_symbol_ids: &'a mut HashSet<SymbolId>,
-}
+            // const { VAR } = process.env
+            if reference.members.len() == 1 {
+                root_symbol_ids.extend(
+                    reference
+                        .destructured
+                        .iter()
+                        .filter(|property| property.key == *query.name())
+                        .flat_map(|property| property.symbol_ids.iter().copied()),
+                );
+            }
+        }
 
-impl<'a> ServiceReference<'a> {
-    pub fn new(
-        service: &'a Service<'a>,
-        reference_node_ids: &'a mut HashSet<NodeId>,
-        reference_symbol_ids: &'a mut HashSet<SymbolId>,
-    ) -> Self {
-        Self {
-            service,
-            reference_node_ids,
-            reference_symbol_ids,
+        for symbol_id in root_symbol_ids {
+            impact.add_declaration(index, symbol_id);
+            impact.symbol_ids.insert(symbol_id);
         }
     }
 
-    pub fn find_references(&mut self, query: &Query) {
-        let scoping = self.service.semantic().scoping();
-        let query_source_path = resolve_import_path(
-            &self.service.root_path,
-            query.symbol_path().to_str().unwrap(),
-        )
-        .unwrap();
-
-        let symbol_source_path = resolve_import_path(
-            &self.service.root_path.join(".."),
-            self.service.source_path.to_str().unwrap(),
-        )
-        .unwrap();
-
-        println!(
-            "Finding references in: {}",
-            self.service.source_path.display()
-        );
-        println!("Query: {:?}", query);
-
-        for id in scoping.symbol_ids() {
-            if scoping.symbol_name(id) == query.symbol() {
-                let declaration = self.service.semantic().symbol_declaration(id);
-
-                if query_source_path == symbol_source_path {
-                    // can we store all of these as symbolIds? and dump the declaration of all of these
-                    // in the file in the end?
-                    // it'll also be easier to maintain the unique symbolIds that way.
-                    //
-                    // One more check in declaration, if it's not an import but a declaration
-                    // then check if the declaration file and query symbol file path is same
-                    // How do I know what's the file of the declaration? source_path? I guess
-                    //
-                    // symbol_id of the declaration being calculated here
-                    if let (Some(node_id), symbol_ids) =
-                        debug_ast_node(declaration, self.service.semantic())
-                    {
-                        self.reference_node_ids.insert(node_id);
-                        self.reference_symbol_ids.extend(symbol_ids);
-                    };
-                } else {
-                    // Check if the declaration is an import or require statement
-                    // If it is then we need to check the source path
-                    // If that's the same as the query or not
-                    //
-                    // How do I know if the declaration is an import?
-                    let import_path =
-                        check_import(&self.service.root_path, declaration, &self.service.semantic);
-
-                    if let Some(import_path) = import_path {
-                        let import_path = self.service.root_path.join(import_path);
-                        let query_source_path = resolve_import_path(
-                            &self.service.root_path,
-                            query.symbol_path().to_str().unwrap(),
-                        )
-                        .unwrap();
-
-                        // there could be symbols with same name in multiple files
-                        // verify if the query symbol is of same imported from same file as
-                        // mentioned in the query
-                        if import_path != query_source_path {
-                            continue;
-                        } else if let (Some(node_id), symbol_ids) =
-                            debug_ast_node(declaration, &self.service.semantic)
-                        {
-                            self.reference_node_ids.insert(node_id);
-                            self.reference_symbol_ids.extend(symbol_ids);
-                        };
-                    }
-                }
+    /// Finds the string literals and template literal quasis whose value is exactly the
+    /// queried one
+    pub fn find_string_references(&mut self, query: &StringQuery) {
+        let (index, impact) = (self.service.index(), &mut self.impact);
 
-                let references = self.service.semantic.symbol_references(id);
-                for reference in references {
-                    self.add_reference_node_ids(reference);
-                }
+        for string in &index.strings {
+            if string.value == *query.value() {
+                impact.add_site(index, &string.site);
             }
         }
     }
 
-    fn add_reference_node_ids(&mut self, reference: &Reference) {
-        let id = reference.symbol_id().unwrap();
-        let semantic = &self.service.semantic;
-        let references = semantic.symbol_references(id);
+    /// Marks the top-level statements touching the changed `lines` as impacted. The
+    /// module-level symbols they declare become impacted symbols of this file. Only the
+    /// innermost units touching the lines are reported.
+    pub fn find_changed_references(&mut self, lines: &[RangeInclusive<usize>]) {
+        if lines.is_empty() {
+            return;
+        }
+
+        let (index, impact) = (self.service.index(), &mut self.impact);
+        let is_changed = |span: &Span| {
+            let (start, end) = (index.line(span.start), index.line(span.end));
+
+            lines
+                .iter()
+                .any(|changed| *changed.start() <= end && start <= *changed.end())
+        };
+
+        for (statement, span) in index.statements.iter().enumerate() {
+            if !is_changed(span) {
+                continue;
+            }
+
+            impact.add_statement(Some(statement));
+
+            for (symbol_id, symbol) in index.symbols.iter().enumerate() {
+                if symbol.root && symbol.declaration.statement == Some(statement) {
+                    impact.symbol_ids.insert(SymbolId::new(symbol_id as u32));
+                }
+            }
+        }
 
-        let (node_id, symbol_ids) =
-            self.add_reference_symbol_and_node_ids(semantic.nodes().get_node(reference.node_id()));
+        let changed_units: Vec<usize> = (0..index.units.len())
+            .filter(|unit| is_changed(&index.units[*unit].span))
+            .collect();
 
-        self.reference_node_ids.extend(node_id);
-        self.reference_symbol_ids.extend(symbol_ids);
+        // the units nested in a unit come after it
+        for unit in &changed_units {
+            let span = index.units[*unit].span;
+            let is_innermost = !changed_units.iter().any(|inner| {
+                let inner_span = index.units[*inner].span;
+                inner > unit && span.start <= inner_span.start && inner_span.end <= span.end
+            });
 
-        for refer in references {
-            if refer.symbol_id() != reference.symbol_id() {
-                self.add_reference_node_ids(refer);
+            if is_innermost {
+                impact.add_unit(Some(*unit));
             }
         }
     }
 
-    fn add_reference_symbol_and_node_ids(&self, node: &AstNode) -> (Option<NodeId>, Vec<SymbolId>) {
-        let semantic = self.service.semantic();
-        let nodes = semantic.nodes();
-        let mut answer = (None, Vec::new());
-
-        for ancestor in nodes.ancestors(node.id()) {
-            match ancestor.kind() {
-                AstKind::Program(_) => {}
-                AstKind::Function(func) => {
-                    if let Some(id) = &func.id {
-                        answer.1.push(id.symbol_id());
-                    }
-                    answer.0 = Some(ancestor.id());
-                }
-                AstKind::VariableDeclarator(vd) => {
-                    get_symbol_ids_from_variable_declarator(vd, &mut answer.1);
-                    answer.0 = Some(ancestor.id());
-                }
-                _ => {
-                    answer.0 = Some(ancestor.id());
-                }
+    /// Marks the handler subscribed by a listener `site` of this file as impacted, `depth`
+    /// hops away from the queried symbols
+    pub fn add_event_handler(&mut self, site: &EventSite, depth: usize) {
+        let (index, impact) = (self.service.index(), &mut self.impact);
+        impact.depth = depth;
+
+        match &site.handler {
+            // bus.on("order:paid", sendReceipt)
+            Some(Handler::Symbol(symbol_id)) => {
+                impact.add_declaration(index, *symbol_id);
+                impact.symbol_ids.insert(*symbol_id);
             }
+            Some(Handler::Inline(handler)) => impact.add_site(index, handler),
+            None => {}
         }
+    }
 
-        answer.1.iter().for_each(|x| {
-            let symbol_name = semantic.scoping().symbol_name(*x);
-            println!("SymbolName: {}", symbol_name);
-        });
+    /// How many hops the code at `span` is away from the queried symbols, `None` unless
+    /// it's part of the impacted top-level statements of this file
+    pub fn impact_depth(&self, span: Span) -> Option<usize> {
+        let statements = &self.service.index().statements;
+
+        self.impact
+            .statements
+            .iter()
+            .filter(|(statement, _)| {
+                let statement_span = statements[**statement];
+                statement_span.start <= span.start && span.end <= statement_span.end
+            })
+            .map(|(_, depth)| *depth)
+            .min()
+    }
 
-        answer
+    /// Forgets the impacted code, e.g. before analyzing other queries
+    pub fn clear(&mut self) {
+        self.impact = Impact::default();
     }
 
     pub fn reference_symbol_ids(&self) -> &HashSet<SymbolId> {
-        self.reference_symbol_ids
+        &self.impact.symbol_ids
     }
 
-    pub fn reference_node_ids(&self) -> &HashSet<NodeId> {
-        self.reference_node_ids
+    /// Indexes of the impacted units in the units of the file, with the kinds of the
+    /// impacted references in them
+    pub fn impacted_units(&self) -> &HashMap<usize, BTreeSet<ReferenceKind>> {
+        &self.impact.units
     }
 
-    pub fn service(&self) -> &'a Service<'a> {
-        self.service
+    /// Bindings the impacted values flow into without referencing them, with the line
+    /// of the reference they flow from
+    pub fn flows(&self) -> &[(usize, Receiver)] {
+        &self.impact.flows
+    }
+
+    pub fn service(&self) -> &Service {
+        &self.service
     }
 }
 
-pub struct Service<'a> {
-    semantic: Semantic<'a>,
+/// A file of the project, reduced to its index. Neither the AST nor the source text is
+/// kept once the index is extracted; the source is only read again for the snippets.
+pub struct Service {
+    index: FileIndex,
+    /// Hash of the contents the index was built from
+    content_hash: u64,
     pub root_path: PathBuf,
     pub source_path: PathBuf,
 }
 
-impl<'a> Service<'a> {
-    pub fn build(root_path: PathBuf, source_path: PathBuf, semantic: Semantic<'a>) -> Result<Self> {
+impl Service {
+    /// Indexes the file at `source_path`, parsing it only when `cache` doesn't hold an
+    /// index of its current contents
+    pub fn build(
+        root_path: PathBuf,
+        source_path: PathBuf,
+        config: &Config,
+        resolver: &ModuleResolver,
+        cache: Option<&IndexCache>,
+    ) -> Result<Self, BumblebeeError> {
+        let source_text =
+            std::fs::read_to_string(&source_path).map_err(BumblebeeError::UnreadableFile)?;
+        let resolve = |specifier: &str| resolver.resolve(&source_path, specifier).ok();
+
+        if let Some(index) = cache.and_then(|cache| cache.load(&source_path, &source_text, resolve))
+        {
+            return Ok(Self {
+                index,
+                content_hash: xxh3_64(source_text.as_bytes()),
+                root_path,
+                source_path,
+            });
+        }
+
+        let module = ParsedModule::parse(&source_path, source_text)?;
+        let mut index = module.with_semantic(|semantic| {
+            FileIndex::build(semantic, &config.events, config.granularity, resolve)
+        });
+
+        // the AST is recovered around syntax errors, the code the parser skipped is missing
+        for error in module.syntax_errors() {
+            let line = index.line(error.offset);
+            let error = BumblebeeError::Syntax(error.message.clone());
+            index.diagnostics.push(Diagnostic::new(Some(line), &error));
+        }
+
+        if module.panicked() {
+            let error = BumblebeeError::Unparsable;
+            index.diagnostics.push(Diagnostic::new(None, &error));
+        }
+
+        index.incomplete = !module.syntax_errors().is_empty() || module.panicked();
+
+        if let Some(cache) = cache {
+            // a cache that can't be written only makes the next run slower
+            cache.store(&source_path, module.source_text(), &index).ok();
+        }
+
         Ok(Self {
-            semantic,
+            index,
+            content_hash: xxh3_64(module.source_text().as_bytes()),
             root_path,
             source_path,
         })
     }
 
-    pub fn semantic(&'a self) -> &'a Semantic<'a> {
-        &self.semantic
+    pub fn index(&self) -> &FileIndex {
+        &self.index
+    }
+
+    /// Source text of the `units` of this file, read again from disk. Fails when the
+    /// file changed since it was indexed, since the spans no longer match its text.
+    pub fn snippets(&self, units: &[usize]) -> Result<Vec<String>, BumblebeeError> {
+        let source_text =
+            std::fs::read_to_string(&self.source_path).map_err(BumblebeeError::UnreadableFile)?;
+
+        if xxh3_64(source_text.as_bytes()) != self.content_hash {
+            return Err(BumblebeeError::ChangedSinceIndexed);
+        }
+
+        Ok(units
+            .iter()
+            .map(|unit| {
+                let span = self.index.units[*unit].span;
+                source_text[(span.start as usize)..(span.end as usize)].to_string()
+            })
+            .collect())
     }
 
-    /// what do we need?
-    /// nodeId, more useful
-    /// we can get node_id from symbol_id but can't go other way around I think
-    /// yes, no symbol_id from node_id
+    /// The symbol of this file named `symbol_name`, e.g. of a query. Module-level
+    /// declarations win over same-named bindings of nested scopes.
     pub fn get_symbol_id(&self, symbol_name: &str) -> Option<SymbolId> {
-        let scoping = self.semantic.scoping();
+        self.index.symbol_id(symbol_name)
+    }
 
-        let symbol_id = scoping
-            .symbol_ids()
-            .find(|&id| scoping.symbol_name(id) == symbol_name);
+    /// Calls of this file matching the event patterns of the config
+    pub fn event_sites(&self) -> &[EventSite] {
+        &self.index.event_sites
+    }
 
-        if let Some(symbol_id) = symbol_id {
-            self.semantic.symbol_declaration(symbol_id);
-        }
+    /// The imports and exports of this file, for the linker
+    pub fn module_interface(&self) -> &ModuleInterface {
+        &self.index.interface
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::query::{EnvQuery, GlobalQuery, Query, SymbolQuery};
+    use crate::testing::{analyze, project, unit_ids};
+
+    #[test]
+    fn global_member_paths() {
+        let dir = project(&[
+            (
+                "storage.js",
+                "export const save = () => window.localStorage.setItem(\"cart\", \"\");\nexport const load = () => localStorage.getItem(\"cart\");\nexport const clear = () => localStorage.setItem(\"cart\", null);\n",
+            ),
+            (
+                "local.js",
+                "const localStorage = new Map();\nexport const save = () => localStorage.setItem(\"cart\", \"\");\n",
+            ),
+        ]);
+        let report = analyze(
+            dir.path(),
+            &[Query::Global(GlobalQuery::new(
+                "window.localStorage.setItem",
+            ))],
+        );
 
-        symbol_id
+        assert_eq!(unit_ids(&report), ["storage.js#save", "storage.js#clear"]);
+    }
+
+    #[test]
+    fn shadowed_locals_not_impacted() {
+        let dir = project(&[
+            (
+                "factory.js",
+                "export const call = () => {};\nexport const direct = () => call();\nexport function local() {\n  const call = () => {};\n  return call();\n}\n",
+            ),
+            (
+                "app.js",
+                "import { call } from \"./factory.js\";\nexport const run = () => call();\nexport const wrap = (call) => call();\n",
+            ),
+        ]);
+        let query = SymbolQuery::new_with_symbol("call".into(), "factory.js".into());
+        let report = analyze(dir.path(), &[Query::Symbol(query)]);
+
+        assert_eq!(
+            unit_ids(&report),
+            [
+                "app.js#<import@L1>",
+                "app.js#run",
+                "factory.js#call",
+                "factory.js#direct"
+            ]
+        );
+    }
+
+    #[test]
+    fn declaration_siblings_not_impacted() {
+        let dir = project(&[(
+            "env.js",
+            "const { API_URL, DEBUG } = process.env;\nexport const endpoint = (path) => `${API_URL}/${path}`;\nexport const isDebug = () => DEBUG === \"true\";\n",
+        )]);
+        let report = analyze(dir.path(), &[Query::Env(EnvQuery::new("API_URL".into()))]);
+
+        assert_eq!(
+            unit_ids(&report),
+            ["env.js#API_URL,DEBUG", "env.js#endpoint"]
+        );
     }
 }
@@ -0,0 +1,53 @@
+//! Helpers for the tests of the crate
+
+use crate::config::Config;
+use crate::core::Bumblebee;
+use crate::query::Query;
+use crate::report::ImpactReport;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// A project made of `files`, by path relative to its root, removed once dropped
+pub fn project(files: &[(&str, &str)]) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+
+    for (path, contents) in files {
+        let path = dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    dir
+}
+
+/// A project analyzed with `config`, without the cache of the previous runs
+pub fn bumblebee(root_path: &Path, config: Config) -> Bumblebee {
+    let config = Config {
+        cache: false,
+        ..config
+    };
+
+    Bumblebee::builder()
+        .root(root_path)
+        .config(config)
+        .build()
+        .unwrap()
+}
+
+/// Analyzes the project at `root_path` with the default config
+pub fn analyze(root_path: &Path, queries: &[Query]) -> ImpactReport {
+    bumblebee(root_path, Config::default())
+        .analyze(queries)
+        .unwrap()
+}
+
+/// Ids of the impacted units of `report`
+pub fn unit_ids(report: &ImpactReport) -> Vec<&str> {
+    report.units.iter().map(|unit| unit.id.as_str()).collect()
+}
+
+/// Logs through the output the tests capture, before `run` sets the subscriber logging
+/// to stderr
+pub fn capture_logs() {
+    tracing_subscriber::fmt().with_test_writer().try_init().ok();
+}
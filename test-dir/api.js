@@ -0,0 +1,3 @@
+// re-exports are followed by the linker: `run` is `call` of factory.js
+export { call as run } from "./factory.js";
+export * from "./storage.js";
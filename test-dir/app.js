@@ -0,0 +1,8 @@
+import { run, save } from "./api.js";
+import * as factory from "./factory.js";
+
+export const start = () => run();
+
+export const boot = () => factory.call();
+
+export const persist = () => save("app", {});
@@ -0,0 +1,10 @@
+// environment variables can be queried with `--env API_URL`
+const { API_URL, DEBUG } = process.env;
+
+export const endpoint = (path) => `${API_URL}/${path}`;
+
+export const timeout = Number(process.env["API_TIMEOUT"] ?? 1000);
+
+export const assetsUrl = import.meta.env.ASSETS_URL || process.env.API_URL;
+
+export const isDebug = () => DEBUG === "true";
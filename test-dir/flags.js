@@ -0,0 +1,10 @@
+// string constants can be queried with `--string checkout.v2`
+const flags = new Set(["checkout.v2"]);
+
+export const isEnabled = (flag) => flags.has(flag);
+
+export const checkoutVersion = () => (isEnabled(`checkout.v2`) ? 2 : 1);
+
+export const trackCheckout = () => {
+  console.log("checkout.v2", checkoutVersion());
+};
@@ -0,0 +1,12 @@
+// the payload of `order:paid` depends on `fellow`, so its handlers are impacted too
+import { fellow } from "utils";
+import { bus } from "./events.js";
+
+export const payOrder = (order) => {
+  fellow();
+  bus.emit("order:paid", { ...order, paid: true });
+};
+
+export const shipOrder = (order) => {
+  bus.emit("order:shipped", order);
+};
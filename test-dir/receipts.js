@@ -0,0 +1,17 @@
+import { bus } from "./events.js";
+
+const sendReceipt = (order) => {
+  console.log("receipt", order.id);
+};
+
+bus.on("order:paid", sendReceipt);
+
+bus.once("order:paid", (order) => {
+  console.log("first payment", order.id);
+});
+
+bus.on("order:shipped", (order) => {
+  console.log("shipped", order.id);
+});
+
+export const resendReceipt = (order) => sendReceipt(order);
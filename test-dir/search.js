@@ -0,0 +1,13 @@
+// third-party symbols can be queried with `--package lodash --symbol debounce`
+import _ from "lodash";
+import debounce from "lodash/debounce";
+
+export const search = debounce((query) => {
+  console.log(query);
+}, 300);
+
+export const resize = _.throttle(() => {}, 100);
+
+export const scroll = _.debounce(() => {
+  search("scroll");
+}, 100);
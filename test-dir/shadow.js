@@ -0,0 +1,9 @@
+// only `greet` uses the imported `fellow`, the local one of `unrelated` shadows it
+import { fellow as hello } from "utils";
+
+export const greet = () => hello();
+
+export const unrelated = () => {
+  const fellow = "local";
+  return fellow;
+};
@@ -0,0 +1,16 @@
+// globals have no declaration, query them with `--global window.localStorage.setItem`
+import { readFile } from "node:fs";
+
+export const save = (key, value) => {
+  window.localStorage.setItem(key, JSON.stringify(value));
+};
+
+export const load = (key) => JSON.parse(localStorage.getItem(key));
+
+export const remember = (key) => {
+  save(key, Date.now());
+};
+
+export const loadUser = () => fetch("/api/user").then((res) => res.json());
+
+export const readConfig = (path) => readFile(path, "utf8");